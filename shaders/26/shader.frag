@@ -1,6 +1,11 @@
 #version 450
 
-layout(binding = 1) uniform sampler2D texSampler;
+layout(set = 1, binding = 0) uniform sampler2D texSampler;
+
+layout(push_constant) uniform PushConstants {
+    mat4 model;
+    vec4 baseColor;
+} pcs;
 
 layout(location = 0) in vec4 fragNormal;
 layout(location = 1) in vec2 fragTexCoord;
@@ -8,5 +13,5 @@ layout(location = 1) in vec2 fragTexCoord;
 layout(location = 0) out vec4 outColor;
 
 void main() {
-    outColor = texture(texSampler, fragTexCoord);
+    outColor = texture(texSampler, fragTexCoord) * pcs.baseColor;
 }
\ No newline at end of file
@@ -1,11 +1,15 @@
 #version 450
 
 layout(binding = 0) uniform UniformBufferObject {
-    mat4 model;
     mat4 view;
     mat4 proj;
 } ubo;
 
+layout(push_constant) uniform PushConstants {
+    mat4 model;
+    vec4 baseColor;
+} pcs;
+
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 layout(location = 2) in vec2 inTexCoord;
@@ -14,7 +18,7 @@ layout(location = 0) out vec4 fragNormal;
 layout(location = 1) out vec2 fragTexCoord;
 
 void main() {
-    gl_Position = ubo.proj * ubo.view * ubo.model * vec4(inPosition, 1.0);
-    fragNormal = ubo.proj * ubo.view * ubo.model * vec4(inNormal, 1.0);
+    gl_Position = ubo.proj * ubo.view * pcs.model * vec4(inPosition, 1.0);
+    fragNormal = ubo.proj * ubo.view * pcs.model * vec4(inNormal, 1.0);
     fragTexCoord = inTexCoord;
 }
\ No newline at end of file
@@ -1,17 +1,23 @@
 pub mod app;
 pub mod appdata;
 pub mod atlas;
-pub mod bufferdata;
+pub mod atlasbuilder;
 pub mod buffers;
 pub mod camera;
+pub mod clock;
 pub mod colorobjects;
 pub mod commandbuffers;
 pub mod commandpool;
 pub mod dephobjects;
 pub mod descriptors;
 pub mod featherapp;
+pub mod flycontroller;
 pub mod framebuffers;
+pub mod goldenimage;
+pub mod handle;
 pub mod images;
+pub mod inputevent;
+pub mod inputstate;
 pub mod instance;
 pub mod logicaldevice;
 pub mod material;
@@ -19,22 +25,42 @@ pub mod math;
 pub mod mesh;
 pub mod meshbuffer;
 pub mod meshbufferdata;
+pub mod meshbuilder;
+pub mod meshbuildercapsule;
+pub mod meshbuildercone;
 pub mod meshbuildercuboid;
+pub mod meshbuildercylinder;
+pub mod meshbuilderdisc;
+pub mod meshbuildergroup;
+pub mod meshbuildericosphere;
+pub mod meshbuilderlathe;
 pub mod meshbuilderobjfile;
-pub mod model;
+pub mod meshbuilderplane;
+pub mod meshbuildertorus;
+pub mod meshbuilderuvsphere;
 pub mod node;
+pub mod nodeiter;
 pub mod objdb;
 pub mod object;
+pub mod orbitcontroller;
 pub mod other;
+pub mod orthographiccamera;
 pub mod perspectivecamera;
 pub mod physicaldevice;
 pub mod pipeline;
+pub mod pushconstants;
 pub mod queuefamilyindices;
+pub mod ray;
+pub mod rgbaimage;
+pub mod samplersettings;
 pub mod scene;
 pub mod shader;
 pub mod swapchain;
 pub mod swapchainsupport;
 pub mod syncobjects;
 pub mod texture;
+pub mod textureloader;
 pub mod uniformbufferobject;
 pub mod vertex;
+pub mod vertexlayout;
+pub mod viewport;
@@ -1,9 +1,7 @@
-use std::mem::size_of;
+use std::collections::HashMap;
 use std::ptr::copy_nonoverlapping as memcpy;
-use std::time::Instant;
 
 use anyhow::{anyhow, Result};
-use cgmath::{vec3, Deg};
 use vulkanalia::loader::{LibloadingLoader, LIBRARY};
 use vulkanalia::prelude::v1_0::*;
 use vulkanalia::window as vk_window;
@@ -15,36 +13,36 @@ use vulkanalia::vk::ExtDebugUtilsExtension;
 use vulkanalia::vk::KhrSurfaceExtension;
 use vulkanalia::vk::KhrSwapchainExtension;
 
-/// Whether the validation layers should be enabled.
-const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
-
 /// The maximum number of frames that can be processed concurrently.
 const MAX_FRAMES_IN_FLIGHT: usize = 2;
 
-use crate::feather::perspectivecamera::PerspectiveCamera;
-use crate::feather::scene::Scene;
-
 use super::appdata::AppData;
-use super::buffers::{create_index_buffer, create_uniform_buffers, create_vertex_buffer};
-use super::camera::Camera;
+use super::atlas::Atlas;
+use super::buffers::create_uniform_buffers;
+use super::clock::{Clock, ClockTick};
 use super::colorobjects::create_color_objects;
-use super::commandbuffers::create_command_buffers;
+use super::commandbuffers::{create_command_buffers, update_command_buffer};
 use super::commandpool::create_command_pool;
 use super::dephobjects::create_depth_objects;
 use super::descriptors::{create_descriptor_pool, create_descriptor_sets};
 use super::featherapp::FeatherApp;
 use super::framebuffers::create_framebuffers;
+use super::images::read_image;
+use super::inputevent::InputEvent;
+use super::inputstate::InputState;
 use super::instance::create_instance;
 use super::logicaldevice::create_logical_device;
-use super::math::*;
-use super::meshbuffer::MeshBuffer;
-use super::model::load_model;
+use super::material::Material;
 use super::physicaldevice::pick_physical_device;
-use super::pipeline::{create_descriptor_set_layout, create_pipeline, create_render_pass};
+use super::pipeline::{
+    create_descriptor_set_layout, create_pipeline, create_render_pass, create_shader_pipelines,
+    has_shader_pipelines,
+};
+use super::rgbaimage::RgbaImage;
 use super::swapchain::Swapchain;
 use super::syncobjects::create_sync_objects;
-use super::texture::{create_texture_image, create_texture_image_view, create_texture_sampler};
 use super::uniformbufferobject::UniformBufferObject;
+use super::viewport::get_scene_viewports;
 
 /// Our Vulkan app.
 pub struct App {
@@ -54,33 +52,62 @@ pub struct App {
     device: Device,
     frame: usize,
     pub resized: bool,
-    start: Instant,
+    clock: Clock,
+    input: InputState,
+    capture_path: Option<String>,
 }
 
-fn create_scene(data: &mut AppData) {
-    let mut scene = Scene::new();
-    let _root_node = scene.create_root_node(Some("Scene root".to_string()));
-    data.scene = scene;
-    let mut camera = PerspectiveCamera::new();
-    camera.set_fov(45.0).set_near_far(0.1, 10.0).set_view(
-        Point3::new(2.0, 2.0, 2.0),
-        Point3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, 1.0),
-    );
-    data.camera = camera;
-    load_model(data).expect("Failed to load model");
+/// Uploads the white atlas and the material used by nodes without a material.
+unsafe fn create_default_material(
+    instance: &Instance,
+    device: &Device,
+    data: &mut AppData,
+) -> Result<()> {
+    data.default_atlas.prepare(
+        instance,
+        device,
+        &data.physical_device,
+        &data.command_pool,
+        &data.graphics_queue,
+    )?;
+    data.default_material.prepare(
+        device,
+        data.material_descriptor_set_layout,
+        &data.default_atlas,
+    )
 }
 
 impl App {
     /// Creates our Vulkan app.
     pub unsafe fn create(window: &Window, app: Box<dyn FeatherApp>) -> Result<Self> {
+        Self::create_internal(Some(window), app, vk::Extent2D::default())
+    }
+
+    /// Creates an app which renders into an offscreen image of the given size instead of a
+    /// window, see `render_offscreen` and `read_offscreen_image`.
+    ///
+    /// No display or presentation support is needed, so this also works with software drivers
+    /// such as lavapipe.
+    pub unsafe fn create_headless(
+        app: Box<dyn FeatherApp>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        Self::create_internal(None, app, vk::Extent2D { width, height })
+    }
+
+    unsafe fn create_internal(
+        window: Option<&Window>,
+        app: Box<dyn FeatherApp>,
+        extent: vk::Extent2D,
+    ) -> Result<Self> {
         let loader = LibloadingLoader::new(LIBRARY)?;
         let entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
         let mut data = AppData {
             app,
-            scene: Scene::default(),
-            camera: PerspectiveCamera::default(),
+            validation: false,
             messenger: vk::DebugUtilsMessengerEXT::default(),
+            headless: window.is_none(),
             surface: vk::SurfaceKHR::default(),
             physical_device: vk::PhysicalDevice::default(),
             msaa_samples: vk::SampleCountFlags::default(),
@@ -89,8 +116,9 @@ impl App {
             swapchain: Swapchain::default(),
             render_pass: vk::RenderPass::default(),
             descriptor_set_layout: vk::DescriptorSetLayout::default(),
+            material_descriptor_set_layout: vk::DescriptorSetLayout::default(),
             pipeline_layout: vk::PipelineLayout::default(),
-            pipeline: vk::Pipeline::default(),
+            pipelines: HashMap::new(),
             framebuffers: Vec::new(),
             command_pool: vk::CommandPool::default(),
             color_image: vk::Image::default(),
@@ -99,15 +127,12 @@ impl App {
             depth_image: vk::Image::default(),
             depth_image_memory: vk::DeviceMemory::default(),
             depth_image_view: vk::ImageView::default(),
-            mip_levels: 0,
-            texture_image: vk::Image::default(),
-            texture_image_memory: vk::DeviceMemory::default(),
-            texture_image_view: vk::ImageView::default(),
-            texture_sampler: vk::Sampler::default(),
-            mesh: 0,
-            mesh_buffer: MeshBuffer::default(),
+            default_atlas: Atlas::new_solid(None, [255, 255, 255, 255]),
+            default_material: Material::new(None),
             uniform_buffers: Vec::new(),
             uniform_buffers_memory: Vec::new(),
+            uniform_buffer_slots: 1,
+            uniform_buffer_stride: 0,
             descriptor_pool: vk::DescriptorPool::default(),
             descriptor_sets: Vec::new(),
             command_buffers: Vec::new(),
@@ -117,10 +142,15 @@ impl App {
             images_in_flight: Vec::new(),
         };
         let instance = create_instance(window, &entry, &mut data)?;
-        data.surface = vk_window::create_surface(&instance, &window, &window)?;
+        if let Some(window) = window {
+            data.surface = vk_window::create_surface(&instance, &window, &window)?;
+        }
         pick_physical_device(&instance, &mut data)?;
         let device = create_logical_device(&entry, &instance, &mut data)?;
-        data.swapchain = Swapchain::create(window, &instance, &device, &data)?;
+        data.swapchain = match window {
+            Some(window) => Swapchain::create(window, &instance, &device, &data)?,
+            None => Swapchain::create_offscreen(&instance, &device, &data, extent)?,
+        };
         data.swapchain.create_image_views(&device)?;
         create_render_pass(&instance, &device, &mut data)?;
         create_descriptor_set_layout(&device, &mut data)?;
@@ -129,12 +159,9 @@ impl App {
         create_color_objects(&instance, &device, &mut data)?;
         create_depth_objects(&instance, &device, &mut data)?;
         create_framebuffers(&device, &mut data)?;
-        create_texture_image(&instance, &device, &mut data)?;
-        create_texture_image_view(&device, &mut data)?;
-        create_texture_sampler(&device, &mut data)?;
-        create_scene(&mut data);
+        create_default_material(&instance, &device, &mut data)?;
         data.app.on_create()?;
-        App::create_mesh_buffers(&instance, &device, &mut data)?;
+        App::prepare_scenes(&instance, &device, &mut data)?;
         create_uniform_buffers(&instance, &device, &mut data)?;
         create_descriptor_pool(&device, &mut data)?;
         create_descriptor_sets(&device, &mut data)?;
@@ -147,47 +174,122 @@ impl App {
             device,
             frame: 0,
             resized: false,
-            start: Instant::now(),
+            clock: Clock::default(),
+            input: InputState::new(),
+            capture_path: None,
         })
     }
 
-    fn prepare_scenes(&mut self) -> Result<()> {
-        for i in 0..self.data.app.get_num_scenes_to_render() {
-            self.data
-                .app
-                .get_scene_to_render(i)
-                .build_missing_mesh_buffers()?;
-        }
-        Ok(())
-    }
-
-    unsafe fn create_mesh_buffers(
+    /// Uploads the mesh buffers and atlases which are not on the GPU yet, and creates the
+    /// descriptor sets of new or changed materials, and the pipelines for every shader and vertex
+    /// layout drawn.
+    unsafe fn prepare_scenes(
         instance: &Instance,
         device: &Device,
         data: &mut AppData,
     ) -> Result<()> {
-        create_vertex_buffer(&instance, &device, data)?;
-        create_index_buffer(&instance, &device, data)?;
+        let mut shaders = Vec::new();
         for i in 0..data.app.get_num_scenes_to_render() {
             let scene = data.app.get_scene_to_render(i);
-            for buffer in scene.buffers.iter_mut() {
-                buffer.prepare(
-                    &instance,
-                    &device,
+            scene.build_missing_mesh_buffers()?;
+            scene.prepare_mesh_buffers(
+                instance,
+                device,
+                &data.physical_device,
+                &data.command_pool,
+                &data.graphics_queue,
+            )?;
+            if scene.needs_prepare() {
+                // Changed materials replace descriptor sets which may still be in use.
+                device.device_wait_idle()?;
+                scene.prepare_atlases(
+                    instance,
+                    device,
                     &data.physical_device,
                     &data.command_pool,
                     &data.graphics_queue,
-                    &scene.meshes,
+                )?;
+                scene.prepare_materials(
+                    device,
+                    data.material_descriptor_set_layout,
+                    &data.default_atlas,
                 )?;
             }
+            // Nodes fall back to the default material until their own one is ready. Meshes
+            // lacking attributes a shader reads get no pipeline for it and are not drawn with it.
+            for (node_handle, _, _) in scene.iter_depth_first() {
+                let node = scene.get_node(node_handle).unwrap();
+                let Some(mesh) = node.get_mesh().and_then(|m| scene.get_mesh(m)) else {
+                    continue;
+                };
+                let material = node.get_material().and_then(|m| scene.get_material(m));
+                for shader in material
+                    .map(|m| m.get_shader())
+                    .into_iter()
+                    .chain([data.default_material.get_shader()])
+                {
+                    if shader.supports(mesh.get_layout())
+                        && !has_shader_pipelines(&data.pipelines, shader, mesh.get_layout())
+                    {
+                        shaders.push((shader.clone(), mesh.get_layout().clone()));
+                    }
+                }
+            }
+        }
+        for (shader, layout) in shaders {
+            create_shader_pipelines(device, data, &shader, &layout)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Returns the clock, e.g. to switch it to a fixed timestep or to step it manually.
+    pub fn get_clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    pub fn get_input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Passes an input event to the app and records it in the input state.
+    ///
+    /// Window events are forwarded by `run`; tests can inject synthetic events instead.
+    pub fn inject_event(&mut self, event: InputEvent) -> Result<()> {
+        self.input.handle_event(&event);
+        self.data.app.on_input(&event)
+    }
+
+    /// Runs the updates of the clock and lets the app prepare the frame.
+    unsafe fn update_app(&mut self) -> Result<()> {
+        let app = &mut self.data.app;
+        run_updates(self.clock.tick(), &mut self.input, |tick, input| {
+            app.on_update(tick.time, tick.delta, tick.frame, input)
+        })?;
+        App::prepare_scenes(&self.instance, &self.device, &mut self.data)?;
+        self.data.app.on_render(self.clock.get_alpha())?;
+        self.reserve_uniform_buffer_slots(self.data.app.get_num_scenes_to_render())?;
+        if let Some(path) = self.data.app.take_capture_request() {
+            self.capture_frame(&path);
         }
         Ok(())
     }
 
+    /// Captures the next rendered frame to a PNG file.
+    ///
+    /// The file is written by `render` or `render_offscreen` once the frame has finished. A
+    /// failed capture is logged by `render`, which still presents the frame, and returned as an
+    /// error by `render_offscreen`.
+    pub fn capture_frame(&mut self, path: &str) {
+        self.capture_path = Some(path.to_string());
+    }
+
     /// Renders a frame for our Vulkan app.
     pub unsafe fn render(&mut self, window: &Window) -> Result<()> {
-        self.prepare_scenes()?;
-        self.data.app.on_render()?;
+        self.update_app()?;
         let in_flight_fence = self.data.in_flight_fences[self.frame];
 
         self.device
@@ -215,6 +317,7 @@ impl App {
         self.data.images_in_flight[image_index] = in_flight_fence;
 
         self.update_uniform_buffer(image_index)?;
+        update_command_buffer(&self.device, &mut self.data, image_index)?;
 
         let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -231,6 +334,18 @@ impl App {
         self.device
             .queue_submit(self.data.graphics_queue, &[submit_info], in_flight_fence)?;
 
+        // A failed capture must not keep the acquired image from being presented.
+        if let Some(path) = self.capture_path.take() {
+            self.device
+                .wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
+            let capture = self
+                .read_swapchain_image(image_index, vk::ImageLayout::PRESENT_SRC_KHR)
+                .and_then(|image| Ok(image.save_png(&path)?));
+            if let Err(e) = capture {
+                log::error!("Could not capture the frame to {}: {}", path, e);
+            }
+        }
+
         let swapchains = &[self.data.swapchain.swapchain];
         let image_indices = &[image_index as u32];
         let present_info = vk::PresentInfoKHR::builder()
@@ -255,36 +370,141 @@ impl App {
         Ok(())
     }
 
-    /// Updates the uniform buffer object for our Vulkan app.
-    unsafe fn update_uniform_buffer(&mut self, image_index: usize) -> Result<()> {
-        // MVP
+    /// Renders a frame of a headless app and waits until it is finished.
+    pub unsafe fn render_offscreen(&mut self) -> Result<()> {
+        if !self.data.headless {
+            return Err(anyhow!(
+                "Offscreen rendering needs an app created with create_headless."
+            ));
+        }
 
-        let time = self.start.elapsed().as_secs_f32();
-        self.data.app.on_update(time)?;
+        self.update_app()?;
 
-        self.data.camera.set_screen_dimention(
-            self.data.swapchain.swapchain_extent.width,
-            self.data.swapchain.swapchain_extent.height,
-        );
+        self.update_uniform_buffer(0)?;
+        update_command_buffer(&self.device, &mut self.data, 0)?;
 
-        let model = Mat4::from_axis_angle(vec3(0.0, 0.0, 1.0), Deg(10.0) * time);
+        let in_flight_fence = self.data.in_flight_fences[0];
+        self.device.reset_fences(&[in_flight_fence])?;
 
-        let ubo = UniformBufferObject {
-            model,
-            view: self.data.camera.get_view(),
-            proj: self.data.camera.get_projection(),
-        };
+        let command_buffers = &[self.data.command_buffers[0]];
+        let submit_info = vk::SubmitInfo::builder().command_buffers(command_buffers);
 
-        // Copy
+        self.device
+            .queue_submit(self.data.graphics_queue, &[submit_info], in_flight_fence)?;
+        self.device
+            .wait_for_fences(&[in_flight_fence], true, u64::MAX)?;
+
+        if let Some(path) = self.capture_path.take() {
+            self.read_offscreen_image()?.save_png(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the last frame rendered by `render_offscreen` back to the CPU.
+    pub unsafe fn read_offscreen_image(&self) -> Result<RgbaImage> {
+        if !self.data.headless {
+            return Err(anyhow!(
+                "Offscreen images exist only in apps created with create_headless."
+            ));
+        }
+
+        self.read_swapchain_image(0, vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+    }
 
+    /// Copies a rendered swapchain image back to the CPU, converting it to RGBA.
+    unsafe fn read_swapchain_image(
+        &self,
+        image_index: usize,
+        layout: vk::ImageLayout,
+    ) -> Result<RgbaImage> {
+        let swapchain = &self.data.swapchain;
+        if !swapchain.readable {
+            return Err(anyhow!(
+                "The surface does not allow reading swapchain images."
+            ));
+        }
+
+        let extent = swapchain.swapchain_extent;
+        let pixels = read_image(
+            &self.instance,
+            &self.device,
+            &self.data,
+            swapchain.swapchain_images[image_index],
+            layout,
+            extent,
+        )?;
+        match swapchain.swapchain_format {
+            vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => {
+                RgbaImage::new(extent.width, extent.height, pixels)
+            }
+            vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => {
+                RgbaImage::from_bgra(extent.width, extent.height, pixels)
+            }
+            format => Err(anyhow!("Cannot read images of format {:?}.", format)),
+        }
+    }
+
+    /// Grows the uniform buffers so they have room for the given number of cameras.
+    unsafe fn reserve_uniform_buffer_slots(&mut self, slots: usize) -> Result<()> {
+        if slots <= self.data.uniform_buffer_slots {
+            return Ok(());
+        }
+
+        // The buffers and descriptor sets may still be used by frames in flight.
+        self.device.device_wait_idle()?;
+        self.device
+            .destroy_descriptor_pool(self.data.descriptor_pool, None);
+        self.data
+            .uniform_buffers_memory
+            .iter()
+            .for_each(|m| self.device.free_memory(*m, None));
+        self.data
+            .uniform_buffers
+            .iter()
+            .for_each(|b| self.device.destroy_buffer(*b, None));
+
+        self.data.uniform_buffer_slots = slots.next_power_of_two();
+        create_uniform_buffers(&self.instance, &self.device, &mut self.data)?;
+        create_descriptor_pool(&self.device, &mut self.data)?;
+        create_descriptor_sets(&self.device, &mut self.data)?;
+        Ok(())
+    }
+
+    /// Updates the uniform buffer objects of the cameras of all scenes.
+    unsafe fn update_uniform_buffer(&mut self, image_index: usize) -> Result<()> {
+        let count = self.data.app.get_num_scenes_to_render();
+        if count == 0 {
+            return Ok(());
+        }
+
+        let extent = self.data.swapchain.swapchain_extent;
+        let viewports = get_scene_viewports(self.data.app.as_mut());
+        let stride = self.data.uniform_buffer_stride;
         let memory = self.device.map_memory(
             self.data.uniform_buffers_memory[image_index],
             0,
-            size_of::<UniformBufferObject>() as u64,
+            stride * count as u64,
             vk::MemoryMapFlags::empty(),
         )?;
 
-        memcpy(&ubo, memory.cast(), 1);
+        for (i, viewport) in viewports.iter().enumerate() {
+            // MVP
+
+            let rect = viewport.to_pixels(extent);
+            let camera = self.data.app.get_camera_to_render_scene(i);
+            camera.set_viewport(rect.extent.width, rect.extent.height);
+
+            let ubo = UniformBufferObject {
+                view: camera.get_view(),
+                proj: camera.get_projection(),
+            };
+
+            // Copy
+
+            let slot = memory.cast::<u8>().add(i * stride as usize);
+            memcpy(&ubo, slot.cast(), 1);
+        }
 
         self.device
             .unmap_memory(self.data.uniform_buffers_memory[image_index]);
@@ -325,17 +545,20 @@ impl App {
         self.data.in_flight_fences.iter().for_each(|f| self.device.destroy_fence(*f, None));
         self.data.render_finished_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
         self.data.image_available_semaphores.iter().for_each(|s| self.device.destroy_semaphore(*s, None));
-        self.data.mesh_buffer.cleanup(&self.device);
-        self.device.destroy_sampler(self.data.texture_sampler, None);
-        self.device.destroy_image_view(self.data.texture_image_view, None);
-        self.device.free_memory(self.data.texture_image_memory, None);
-        self.device.destroy_image(self.data.texture_image, None);
+        for i in 0..self.data.app.get_num_scenes_to_render() {
+            self.data.app.get_scene_to_render(i).destroy(&self.device);
+        }
+        self.data.default_material.cleanup(&self.device);
+        self.data.default_atlas.cleanup(&self.device);
         self.device.destroy_command_pool(self.data.command_pool, None);
+        self.device.destroy_descriptor_set_layout(self.data.material_descriptor_set_layout, None);
         self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
         self.device.destroy_device(None);
-        self.instance.destroy_surface_khr(self.data.surface, None);
+        if !self.data.headless {
+            self.instance.destroy_surface_khr(self.data.surface, None);
+        }
 
-        if VALIDATION_ENABLED {
+        if self.data.validation {
             self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
         }
 
@@ -357,7 +580,10 @@ impl App {
         self.device.free_memory(self.data.color_image_memory, None);
         self.device.destroy_image(self.data.color_image, None);
         self.data.framebuffers.iter().for_each(|f| self.device.destroy_framebuffer(*f, None));
-        self.device.destroy_pipeline(self.data.pipeline, None);
+        for pipelines in self.data.pipelines.drain().flat_map(|(_, p)| p.into_values()) {
+            self.device.destroy_pipeline(pipelines.opaque, None);
+            self.device.destroy_pipeline(pipelines.transparent, None);
+        }
         self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
         self.device.destroy_render_pass(self.data.render_pass, None);
         self.data.swapchain.destroy(&self.device);
@@ -372,7 +598,7 @@ impl App {
                 Event::WindowEvent { event, .. } => match event {
                     // Render a frame if our Vulkan app is not being destroyed.
                     WindowEvent::RedrawRequested if !elwt.exiting() && !minimized => {
-                        unsafe { self.render(&window) }.unwrap();
+                        unsafe { self.render(window) }.unwrap();
                     }
                     // Mark the window as having been resized.
                     WindowEvent::Resized(size) => {
@@ -390,7 +616,12 @@ impl App {
                             self.destroy();
                         }
                     }
-                    _ => {}
+                    // Forward input to the app.
+                    event => {
+                        if let Some(event) = InputEvent::from_window_event(&event) {
+                            self.inject_event(event).unwrap();
+                        }
+                    }
                 },
                 _ => {}
             }
@@ -398,3 +629,125 @@ impl App {
         Ok(())
     }
 }
+
+/// Runs an update for every tick. The deltas and presses collected since the last update go to
+/// the first tick only; without ticks they are kept for the next frame.
+fn run_updates(
+    ticks: Vec<ClockTick>,
+    input: &mut InputState,
+    mut update: impl FnMut(ClockTick, &InputState) -> Result<()>,
+) -> Result<()> {
+    for tick in ticks {
+        update(tick, input)?;
+        input.end_frame();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::camera::Camera;
+    use crate::feather::clock::ClockMode;
+    use crate::feather::inputevent::{Key, MouseButton};
+    use crate::feather::math::Vec2;
+    use crate::feather::perspectivecamera::PerspectiveCamera;
+    use crate::feather::scene::Scene;
+
+    /// An app drawing an empty scene.
+    struct EmptyApp {
+        scene: Scene,
+        camera: PerspectiveCamera,
+    }
+
+    impl FeatherApp for EmptyApp {
+        fn on_create(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_render(&mut self, _alpha: f32) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_update(&mut self, _: f32, _: f32, _: u64, _: &InputState) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_destroy(&mut self) {}
+
+        fn get_num_scenes_to_render(&self) -> usize {
+            1
+        }
+
+        fn get_scene_to_render(&mut self, _scene_index: usize) -> &mut Scene {
+            &mut self.scene
+        }
+
+        fn get_camera_to_render_scene(&mut self, _scene_index: usize) -> &mut dyn Camera {
+            &mut self.camera
+        }
+    }
+
+    #[test]
+    fn test_input_per_update() {
+        let mut clock = Clock::new(ClockMode::FixedTimestep(0.0625));
+        let mut input = InputState::new();
+        let mut seen = Vec::new();
+        let mut record = |tick: ClockTick, input: &InputState| {
+            seen.push((
+                tick.frame,
+                input.get_mouse_delta(),
+                input.was_key_pressed(Key::W),
+                input.is_key_down(Key::W),
+            ));
+            Ok(())
+        };
+
+        input.handle_event(&InputEvent::MouseMoved { x: 0.0, y: 0.0 });
+        input.handle_event(&InputEvent::MouseMoved { x: 4.0, y: 2.0 });
+        input.handle_event(&InputEvent::KeyPressed(Key::W));
+        input.handle_event(&InputEvent::MouseButtonPressed(MouseButton::Left));
+
+        // A frame without updates keeps its input for the next one.
+        run_updates(clock.advance(0.03125), &mut input, &mut record).unwrap();
+        assert!(input.was_button_pressed(MouseButton::Left));
+
+        // Only the first of several updates sees the deltas and presses; held keys stay down.
+        run_updates(clock.advance(0.15625), &mut input, &mut record).unwrap();
+        let delta = Vec2::new(4.0, 2.0);
+        let zero = Vec2::new(0.0, 0.0);
+        assert_eq!(
+            seen,
+            [
+                (0, delta, true, true),
+                (1, zero, false, true),
+                (2, zero, false, true)
+            ]
+        );
+        assert!(!input.was_button_pressed(MouseButton::Left));
+    }
+
+    #[test]
+    #[ignore = "needs a Vulkan driver such as lavapipe"]
+    fn test_capture_frame_error() {
+        let app = EmptyApp {
+            scene: Scene::new(),
+            camera: PerspectiveCamera::new(),
+        };
+        let path = std::env::temp_dir().join("feather-missing-directory/frame.png");
+        unsafe {
+            let mut app = App::create_headless(Box::new(app), 16, 16).unwrap();
+            app.capture_frame(path.to_str().unwrap());
+            let result = app.render_offscreen();
+
+            // The request is dropped, and the next frames render as usual.
+            let next = app
+                .render_offscreen()
+                .and_then(|_| app.read_offscreen_image());
+            app.destroy();
+            assert!(result.is_err());
+            assert!(next.is_ok());
+        }
+        assert!(!path.exists());
+    }
+}
@@ -1,19 +1,23 @@
+use std::collections::HashMap;
+
 use vulkanalia::prelude::v1_0::*;
 
+use super::atlas::Atlas;
 use super::featherapp::FeatherApp;
-use super::meshbuffer::MeshBuffer;
-use super::perspectivecamera::PerspectiveCamera;
-use super::scene::Scene;
+use super::material::Material;
+use super::pipeline::ShaderPipelines;
 use super::swapchain::Swapchain;
+use super::vertexlayout::VertexLayout;
 
 /// The Vulkan handles and associated properties used by our Vulkan app.
 pub struct AppData {
     pub app: Box<dyn FeatherApp>,
-    pub scene: Scene,
-    pub camera: PerspectiveCamera,
     // Debug
+    pub validation: bool,
     pub messenger: vk::DebugUtilsMessengerEXT,
     // Surface
+    /// Whether the app renders to an offscreen image instead of a window surface.
+    pub headless: bool,
     pub surface: vk::SurfaceKHR,
     // Physical Device / Logical Device
     pub physical_device: vk::PhysicalDevice,
@@ -25,8 +29,10 @@ pub struct AppData {
     // Pipeline
     pub render_pass: vk::RenderPass,
     pub descriptor_set_layout: vk::DescriptorSetLayout,
+    pub material_descriptor_set_layout: vk::DescriptorSetLayout,
     pub pipeline_layout: vk::PipelineLayout,
-    pub pipeline: vk::Pipeline,
+    /// The pipelines by shader name and vertex layout.
+    pub pipelines: HashMap<String, HashMap<VertexLayout, ShaderPipelines>>,
     // Framebuffers
     pub framebuffers: Vec<vk::Framebuffer>,
     // Command Pool
@@ -39,18 +45,16 @@ pub struct AppData {
     pub depth_image: vk::Image,
     pub depth_image_memory: vk::DeviceMemory,
     pub depth_image_view: vk::ImageView,
-    // Texture
-    pub mip_levels: u32,
-    pub texture_image: vk::Image,
-    pub texture_image_memory: vk::DeviceMemory,
-    pub texture_image_view: vk::ImageView,
-    pub texture_sampler: vk::Sampler,
-    // Model
-    pub mesh: usize,
+    // Materials
+    pub default_atlas: Atlas,
+    pub default_material: Material,
     // Buffers
-    pub mesh_buffer: MeshBuffer,
     pub uniform_buffers: Vec<vk::Buffer>,
     pub uniform_buffers_memory: Vec<vk::DeviceMemory>,
+    /// The number of cameras each uniform buffer has room for.
+    pub uniform_buffer_slots: usize,
+    /// The distance in bytes between the uniform buffer objects of two cameras.
+    pub uniform_buffer_stride: u64,
     // Descriptors
     pub descriptor_pool: vk::DescriptorPool,
     pub descriptor_sets: Vec<vk::DescriptorSet>,
@@ -1 +1,257 @@
-pub struct Atlas {}
+use std::ptr::copy_nonoverlapping as memcpy;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+use vulkanalia::vk::{CommandPool, PhysicalDevice, Queue};
+
+use super::buffers::create_buffer;
+use super::handle::Handle;
+use super::images::{
+    copy_buffer_to_image, create_image, create_image_view, generate_mipmaps,
+    transition_image_layout,
+};
+use super::object::Object;
+use super::textureloader::TextureError;
+
+/// The format of the GPU image created for an atlas.
+const ATLAS_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+/// An RGBA8 image uploaded to the GPU with a full mip chain.
+///
+/// Textures reference a window of an atlas, so several textures can share one image.
+pub struct Atlas {
+    name: Option<String>,
+    handle: Handle<Atlas>,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    mip_levels: u32,
+    image: Option<vk::Image>,
+    image_memory: Option<vk::DeviceMemory>,
+    image_view: Option<vk::ImageView>,
+}
+
+impl Object for Atlas {
+    fn get_name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name)
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn set_handle(&mut self, handle: Handle<Self>) {
+        self.handle = handle;
+    }
+
+    fn get_handle(&self) -> Handle<Self> {
+        self.handle
+    }
+}
+
+impl Atlas {
+    /// Creates an atlas from tightly packed RGBA8 pixels.
+    pub fn new(
+        name: Option<String>,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> Result<Self, TextureError> {
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+            return Err(TextureError::InvalidSize(width, height));
+        }
+        Ok(Self {
+            name,
+            handle: Handle::invalid(),
+            width,
+            height,
+            pixels,
+            mip_levels: (width.max(height) as f32).log2().floor() as u32 + 1,
+            image: None,
+            image_memory: None,
+            image_view: None,
+        })
+    }
+
+    /// Creates a 1x1 atlas filled with a single color.
+    pub fn new_solid(name: Option<String>, rgba: [u8; 4]) -> Self {
+        Self::new(name, 1, 1, rgba.to_vec()).unwrap()
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get_mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// Shortens the mip chain to at most the given number of levels, at least 1. Must be set
+    /// before the atlas is prepared.
+    pub fn set_max_mip_levels(&mut self, max_mip_levels: u32) {
+        self.mip_levels = self.mip_levels.min(max_mip_levels.max(1));
+    }
+
+    pub(crate) fn get_image_view(&self) -> Option<vk::ImageView> {
+        self.image_view
+    }
+
+    pub fn is_prepared(&self) -> bool {
+        self.image_view.is_some()
+    }
+
+    /// Uploads the pixels to a device local image and generates its mipmaps.
+    pub unsafe fn prepare(
+        &mut self,
+        instance: &Instance,
+        device: &Device,
+        physical_device: &PhysicalDevice,
+        command_pool: &CommandPool,
+        graphics_queue: &Queue,
+    ) -> Result<()> {
+        self.cleanup(device);
+
+        let max_size = instance
+            .get_physical_device_properties(*physical_device)
+            .limits
+            .max_image_dimension_2d;
+        if self.width > max_size || self.height > max_size {
+            return Err(anyhow!(
+                "Atlas size {}x{} exceeds the device limit of {}.",
+                self.width,
+                self.height,
+                max_size
+            ));
+        }
+
+        // Create (staging)
+
+        let size = self.pixels.len() as u64;
+
+        let (staging_buffer, staging_buffer_memory) = create_buffer(
+            instance,
+            device,
+            physical_device,
+            size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+
+        // The staging buffer is freed whether or not the upload succeeds.
+        let upload = (|| -> Result<vk::Image> {
+            // Copy (staging)
+
+            let memory =
+                device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
+
+            memcpy(self.pixels.as_ptr(), memory.cast(), self.pixels.len());
+
+            device.unmap_memory(staging_buffer_memory);
+
+            // Create (image)
+
+            let (image, image_memory) = create_image(
+                instance,
+                device,
+                physical_device,
+                self.width,
+                self.height,
+                self.mip_levels,
+                vk::SampleCountFlags::_1,
+                ATLAS_FORMAT,
+                vk::ImageTiling::OPTIMAL,
+                vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::TRANSFER_SRC,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            )?;
+
+            self.image = Some(image);
+            self.image_memory = Some(image_memory);
+
+            // Transition + Copy (image)
+
+            transition_image_layout(
+                device,
+                command_pool,
+                graphics_queue,
+                image,
+                ATLAS_FORMAT,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                self.mip_levels,
+            )?;
+
+            copy_buffer_to_image(
+                device,
+                command_pool,
+                graphics_queue,
+                staging_buffer,
+                image,
+                self.width,
+                self.height,
+            )?;
+
+            Ok(image)
+        })();
+
+        // Cleanup
+
+        device.destroy_buffer(staging_buffer, None);
+        device.free_memory(staging_buffer_memory, None);
+        let image = upload?;
+
+        // Mipmaps
+
+        generate_mipmaps(
+            instance,
+            device,
+            physical_device,
+            command_pool,
+            graphics_queue,
+            image,
+            ATLAS_FORMAT,
+            self.width,
+            self.height,
+            self.mip_levels,
+        )?;
+
+        // Image View
+
+        self.image_view = Some(create_image_view(
+            device,
+            image,
+            ATLAS_FORMAT,
+            vk::ImageAspectFlags::COLOR,
+            self.mip_levels,
+        )?);
+
+        Ok(())
+    }
+
+    pub fn cleanup(&mut self, device: &Device) {
+        unsafe {
+            if let Some(image_view) = self.image_view.take() {
+                device.destroy_image_view(image_view, None);
+            }
+            if let Some(image) = self.image.take() {
+                device.destroy_image(image, None);
+            }
+            if let Some(image_memory) = self.image_memory.take() {
+                device.free_memory(image_memory, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use super::atlas::Atlas;
+use super::scene::Scene;
+use super::texture::Texture;
+use super::textureloader::{self, TextureError};
+
+/// The pixel rectangle of a packed image inside an atlas, without its gutter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Packs many images into a single atlas.
+///
+/// Every image is surrounded by a gutter which repeats its border pixels, so filtering does
+/// not bleed neighbouring images into each other. The padding is rounded up to a power of two
+/// `2^k`, images start at multiples of it and their gutter reaches the next multiple past their
+/// far edge plus the padding. This keeps mip levels 0 to `k` free of bleeding; the atlas mip
+/// chain is cut off after them, so a padding of 2 gives 2 levels and 8 gives 4.
+pub struct AtlasBuilder {
+    name: Option<String>,
+    padding: u32,
+    max_size: u32,
+    images: Vec<Atlas>,
+}
+
+impl AtlasBuilder {
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            name,
+            padding: 2,
+            max_size: 4096,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn set_padding(&mut self, padding: u32) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the maximum width and height of the packed atlas.
+    pub fn set_max_size(&mut self, max_size: u32) -> &mut Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Adds an image and returns its index in the textures returned by `build`.
+    pub fn add_image(&mut self, image: Atlas) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    pub fn add_png_file(&mut self, path: &str) -> Result<usize, TextureError> {
+        Ok(self.add_image(textureloader::load_png_file(path)?))
+    }
+
+    /// Packs the images into a new atlas of the scene and returns one texture per image.
+    pub fn build(&self, scene: &mut Scene) -> Result<Vec<Texture>, TextureError> {
+        let (atlas, rects) = self.pack()?;
+        let (width, height) = (atlas.get_width() as f32, atlas.get_height() as f32);
+        let handle = scene.atlases.add(atlas)?;
+        Ok(rects
+            .iter()
+            .map(|r| {
+                Texture::new(
+                    handle,
+                    r.x as f32 / width,
+                    r.y as f32 / height,
+                    r.width as f32 / width,
+                    r.height as f32 / height,
+                )
+            })
+            .collect())
+    }
+
+    /// Returns the gutter before every image, which is also the alignment of the images.
+    fn get_gutter(&self) -> u32 {
+        match self.padding {
+            0 => 0,
+            padding => padding.next_power_of_two(),
+        }
+    }
+
+    /// Returns the number of mip levels without bleeding.
+    fn get_mip_levels(&self) -> u32 {
+        self.get_gutter().max(1).trailing_zeros() + 1
+    }
+
+    /// Returns the size of an image's cell: the image and its gutter, rounded up to the
+    /// alignment.
+    fn cell_size(&self, size: u32) -> u32 {
+        let gutter = self.get_gutter();
+        size.next_multiple_of(gutter.max(1)) + 2 * gutter
+    }
+
+    /// Packs the images and returns the atlas with the rectangle of every image.
+    pub fn pack(&self) -> Result<(Atlas, Vec<AtlasRect>), TextureError> {
+        let sizes = self
+            .images
+            .iter()
+            .map(|i| {
+                (
+                    self.cell_size(i.get_width()),
+                    self.cell_size(i.get_height()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let area = sizes
+            .iter()
+            .map(|(w, h)| *w as u64 * *h as u64)
+            .sum::<u64>();
+        let widest = sizes.iter().map(|(w, _)| *w).max().unwrap_or(1);
+        let mut width = ((area as f64).sqrt().ceil() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        let (positions, height) = loop {
+            if width > self.max_size {
+                return Err(TextureError::AtlasFull(self.max_size));
+            }
+            let (positions, used_height) = pack_shelves(&sizes, width);
+            let height = used_height.max(1).next_power_of_two();
+            if height <= self.max_size {
+                break (positions, height);
+            }
+            width *= 2;
+        };
+
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        let mut rects = Vec::with_capacity(self.images.len());
+        let gutter = self.get_gutter();
+        for (image, (x, y)) in self.images.iter().zip(positions) {
+            let rect = AtlasRect {
+                x: x + gutter,
+                y: y + gutter,
+                width: image.get_width(),
+                height: image.get_height(),
+            };
+            self.blit_with_gutter(image, rect, width, &mut pixels);
+            rects.push(rect);
+        }
+
+        let mut atlas = Atlas::new(self.name.clone(), width, height, pixels)?;
+        atlas.set_max_mip_levels(self.get_mip_levels());
+        Ok((atlas, rects))
+    }
+
+    /// Copies the image into its rectangle and repeats its border pixels over the whole cell.
+    fn blit_with_gutter(
+        &self,
+        image: &Atlas,
+        rect: AtlasRect,
+        atlas_width: u32,
+        pixels: &mut [u8],
+    ) {
+        let gutter = self.get_gutter() as i64;
+        let cell_width = self.cell_size(rect.width) as i64;
+        let cell_height = self.cell_size(rect.height) as i64;
+        let source = image.get_pixels();
+        for y in -gutter..cell_height - gutter {
+            let source_y = y.clamp(0, rect.height as i64 - 1) as usize;
+            for x in -gutter..cell_width - gutter {
+                let source_x = x.clamp(0, rect.width as i64 - 1) as usize;
+                let from = (source_y * rect.width as usize + source_x) * 4;
+                let to_x = (rect.x as i64 + x) as usize;
+                let to_y = (rect.y as i64 + y) as usize;
+                let to = (to_y * atlas_width as usize + to_x) * 4;
+                pixels[to..to + 4].copy_from_slice(&source[from..from + 4]);
+            }
+        }
+    }
+}
+
+/// Places the rectangles on shelves, tallest first, and returns their positions in the input
+/// order together with the used height.
+fn pack_shelves(sizes: &[(u32, u32)], width: u32) -> (Vec<(u32, u32)>, u32) {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| {
+        (
+            std::cmp::Reverse(sizes[*i].1),
+            std::cmp::Reverse(sizes[*i].0),
+        )
+    });
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x + w > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions[i] = (x, y);
+        x += w;
+        shelf_height = shelf_height.max(h);
+    }
+    (positions, y + shelf_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, value: u8) -> Atlas {
+        Atlas::new(
+            None,
+            width,
+            height,
+            vec![value; (width * height * 4) as usize],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pack() {
+        let mut builder = AtlasBuilder::new(None);
+        builder.set_padding(1);
+        let sizes = [(10, 20), (30, 5), (7, 7), (16, 16), (1, 1)];
+        for (i, (w, h)) in sizes.iter().enumerate() {
+            builder.add_image(solid(*w, *h, i as u8 + 1));
+        }
+
+        let (atlas, rects) = builder.pack().unwrap();
+        assert!(atlas.get_width().is_power_of_two() && atlas.get_height().is_power_of_two());
+
+        let padded = |r: &AtlasRect| (r.x - 1, r.y - 1, r.x + r.width + 1, r.y + r.height + 1);
+        for (i, a) in rects.iter().enumerate() {
+            assert_eq!((a.width, a.height), sizes[i]);
+            let (x0, y0, x1, y1) = padded(a);
+            assert!(x1 <= atlas.get_width() && y1 <= atlas.get_height());
+            for b in &rects[i + 1..] {
+                let (bx0, by0, bx1, by1) = padded(b);
+                assert!(x1 <= bx0 || bx1 <= x0 || y1 <= by0 || by1 <= y0);
+            }
+        }
+
+        // The gutter repeats the border of the image.
+        let pixel = |x: u32, y: u32| atlas.get_pixels()[((y * atlas.get_width() + x) * 4) as usize];
+        for (i, r) in rects.iter().enumerate() {
+            assert_eq!(pixel(r.x - 1, r.y - 1), i as u8 + 1);
+            assert_eq!(pixel(r.x + r.width, r.y + r.height - 1), i as u8 + 1);
+        }
+    }
+
+    #[test]
+    fn test_build_textures() {
+        let mut scene = Scene::new();
+        let mut builder = AtlasBuilder::new(Some("Sprites".to_string()));
+        builder.set_padding(0);
+        builder.add_image(solid(32, 32, 1));
+        builder.add_image(solid(32, 32, 2));
+
+        let textures = builder.build(&mut scene).unwrap();
+        let atlas = scene.get_atlas(textures[0].get_atlas()).unwrap();
+        assert_eq!((atlas.get_width(), atlas.get_height()), (64, 32));
+        assert_eq!(textures[0].get_u(), (0.0, 0.5));
+        assert_eq!(textures[1].get_u(), (0.5, 1.0));
+        assert_eq!(textures[1].get_v(), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_no_bleeding_in_mip_levels() {
+        let mut builder = AtlasBuilder::new(None);
+        builder.set_padding(3);
+        let sizes = [(13, 7), (5, 21), (1, 1), (17, 9), (8, 8), (3, 30)];
+        for (i, (w, h)) in sizes.iter().enumerate() {
+            builder.add_image(solid(*w, *h, 10 * (i as u8 + 1)));
+        }
+        let (atlas, rects) = builder.pack().unwrap();
+
+        // A padding of 3 is rounded up to 4, which keeps levels 0 to 2 clean.
+        assert_eq!(atlas.get_mip_levels(), 3);
+
+        let mut level = atlas
+            .get_pixels()
+            .iter()
+            .map(|p| *p as f32)
+            .collect::<Vec<_>>();
+        let (mut width, mut height) = (atlas.get_width() as usize, atlas.get_height() as usize);
+        for k in 0..atlas.get_mip_levels() {
+            // Every texel bilinear filtering reads for a point inside an image has its value.
+            let scale = (1 << k) as f32;
+            for (i, r) in rects.iter().enumerate() {
+                let texels = |from: u32, size: u32, limit: usize| {
+                    let first = (from as f32 / scale - 0.5).floor().max(0.0) as usize;
+                    let last = ((from + size) as f32 / scale - 0.5).floor() as usize + 1;
+                    first..=last.min(limit - 1)
+                };
+                for y in texels(r.y, r.height, height) {
+                    for x in texels(r.x, r.width, width) {
+                        let value = level[(y * width + x) * 4];
+                        assert_eq!(value, 10.0 * (i as f32 + 1.0), "level {} image {}", k, i);
+                    }
+                }
+            }
+
+            // Box filter into the next level, like the blits generating the mip chain.
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            let mut next = vec![0.0; next_width * next_height * 4];
+            for y in 0..next_height {
+                for x in 0..next_width {
+                    for c in 0..4 {
+                        let at = |x: usize, y: usize| {
+                            level[(y.min(height - 1) * width + x.min(width - 1)) * 4 + c]
+                        };
+                        next[(y * next_width + x) * 4 + c] = (at(2 * x, 2 * y)
+                            + at(2 * x + 1, 2 * y)
+                            + at(2 * x, 2 * y + 1)
+                            + at(2 * x + 1, 2 * y + 1))
+                            / 4.0;
+                    }
+                }
+            }
+            (level, width, height) = (next, next_width, next_height);
+        }
+    }
+
+    #[test]
+    fn test_atlas_full() {
+        let mut builder = AtlasBuilder::new(None);
+        builder.set_max_size(64);
+        builder.add_image(solid(60, 60, 1));
+        builder.add_image(solid(60, 60, 1));
+        assert!(matches!(builder.pack(), Err(TextureError::AtlasFull(64))));
+    }
+}
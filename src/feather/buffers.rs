@@ -1,5 +1,4 @@
 use std::mem::size_of;
-use std::ptr::copy_nonoverlapping as memcpy;
 
 use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
@@ -13,136 +12,6 @@ use super::uniformbufferobject::UniformBufferObject;
 // Buffers
 //================================================
 
-pub unsafe fn create_vertex_buffer(
-    instance: &Instance,
-    device: &Device,
-    data: &mut AppData,
-) -> Result<()> {
-    // Create (staging)
-
-    if data.mesh_buffer.vertex_buffer.is_some() {
-        return Ok(());
-    }
-
-    let mesh = data.scene.get_mesh(data.mesh).unwrap();
-
-    let size = mesh.data_size_for_vertexes() as u64;
-
-    let (staging_buffer, staging_buffer_memory) = create_buffer(
-        instance,
-        device,
-        &data.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    )?;
-
-    // Copy (staging)
-
-    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
-
-    memcpy(mesh.vertices.as_ptr(), memory.cast(), mesh.vertices.len());
-
-    device.unmap_memory(staging_buffer_memory);
-
-    // Create (vertex)
-
-    let (vertex_buffer, vertex_buffer_memory) = create_buffer(
-        instance,
-        device,
-        &data.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )?;
-
-    data.mesh_buffer.vertex_buffer = Some(vertex_buffer);
-    data.mesh_buffer.vertex_buffer_memory = Some(vertex_buffer_memory);
-
-    // Copy (vertex)
-
-    copy_buffer(
-        device,
-        &data.command_pool,
-        &data.graphics_queue,
-        staging_buffer,
-        vertex_buffer,
-        size,
-    )?;
-
-    // Cleanup
-
-    device.destroy_buffer(staging_buffer, None);
-    device.free_memory(staging_buffer_memory, None);
-
-    Ok(())
-}
-
-pub unsafe fn create_index_buffer(
-    instance: &Instance,
-    device: &Device,
-    data: &mut AppData,
-) -> Result<()> {
-    // Create (staging)
-
-    if data.mesh_buffer.index_buffer.is_some() {
-        return Ok(());
-    }
-
-    let mesh = data.scene.get_mesh(data.mesh).unwrap();
-
-    let size = mesh.data_size_for_indexes() as u64;
-
-    let (staging_buffer, staging_buffer_memory) = create_buffer(
-        instance,
-        device,
-        &data.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    )?;
-
-    // Copy (staging)
-
-    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
-
-    memcpy(mesh.indices.as_ptr(), memory.cast(), mesh.indices.len());
-
-    device.unmap_memory(staging_buffer_memory);
-
-    // Create (index)
-
-    let (index_buffer, index_buffer_memory) = create_buffer(
-        instance,
-        device,
-        &data.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )?;
-
-    data.mesh_buffer.index_buffer = Some(index_buffer);
-    data.mesh_buffer.index_buffer_memory = Some(index_buffer_memory);
-
-    // Copy (index)
-
-    copy_buffer(
-        device,
-        &data.command_pool,
-        &data.graphics_queue,
-        staging_buffer,
-        index_buffer,
-        size,
-    )?;
-
-    // Cleanup
-
-    device.destroy_buffer(staging_buffer, None);
-    device.free_memory(staging_buffer_memory, None);
-
-    Ok(())
-}
-
 pub unsafe fn create_uniform_buffers(
     instance: &Instance,
     device: &Device,
@@ -151,12 +20,22 @@ pub unsafe fn create_uniform_buffers(
     data.uniform_buffers.clear();
     data.uniform_buffers_memory.clear();
 
+    // Every camera gets its own uniform buffer object, bound with a dynamic offset.
+    let alignment = instance
+        .get_physical_device_properties(data.physical_device)
+        .limits
+        .min_uniform_buffer_offset_alignment
+        .max(1);
+    data.uniform_buffer_stride =
+        (size_of::<UniformBufferObject>() as u64).next_multiple_of(alignment);
+    data.uniform_buffer_slots = data.uniform_buffer_slots.max(1);
+
     for _ in 0..data.swapchain.swapchain_images.len() {
         let (uniform_buffer, uniform_buffer_memory) = create_buffer(
             instance,
             device,
             &data.physical_device,
-            size_of::<UniformBufferObject>() as u64,
+            data.uniform_buffer_stride * data.uniform_buffer_slots as u64,
             vk::BufferUsageFlags::UNIFORM_BUFFER,
             vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
         )?;
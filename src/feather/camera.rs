@@ -1,6 +1,72 @@
-use super::math::Mat4;
+use cgmath::SquareMatrix;
+use vulkanalia::prelude::v1_0::*;
+
+use super::math::{Mat4, Point3, Vec2, Vec3, Vec4};
+use super::ray::Ray;
 
 pub trait Camera {
     fn get_projection(&self) -> Mat4;
     fn get_view(&self) -> Mat4;
+    fn set_view(&mut self, eye_position: Point3, target_position: Point3, up_vector: Vec3);
+    /// Called before every frame with the size in pixels of the area the camera renders to.
+    fn set_viewport(&mut self, width: u32, height: u32);
+
+    /// Returns the world space ray through a pixel of the window, for a camera rendering to the
+    /// given pixel rectangle (see `Viewport::to_pixels`). `None` if the camera's matrices cannot
+    /// be inverted.
+    fn screen_to_ray(&self, pixel: Vec2, viewport: vk::Rect2D) -> Option<Ray> {
+        let inverse = (self.get_projection() * self.get_view()).invert()?;
+        // The projections already flip Y and map depth to 0..1 for Vulkan, so normalized
+        // device Y points down like the pixels do and the near plane is at depth 0.
+        let x = 2.0 * (pixel.x - viewport.offset.x as f32) / viewport.extent.width as f32 - 1.0;
+        let y = 2.0 * (pixel.y - viewport.offset.y as f32) / viewport.extent.height as f32 - 1.0;
+        let unproject =
+            |depth: f32| Point3::from_homogeneous(inverse * Vec4::new(x, y, depth, 1.0));
+        let (near, far) = (unproject(0.0), unproject(1.0));
+        Some(Ray::new(near, far - near))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::perspectivecamera::PerspectiveCamera;
+    use cgmath::InnerSpace;
+
+    #[test]
+    fn test_screen_to_ray() {
+        let mut camera = PerspectiveCamera::new();
+        camera.set_screen_dimention(400, 200);
+        camera.set_view(
+            Point3::new(0.0, -5.0, 1.0),
+            Point3::new(0.0, 0.0, 1.0),
+            Vec3::unit_z(),
+        );
+        // The camera renders to the right half of an 800x200 window.
+        let viewport = vk::Rect2D {
+            offset: vk::Offset2D { x: 400, y: 0 },
+            extent: vk::Extent2D {
+                width: 400,
+                height: 200,
+            },
+        };
+
+        let ray = camera
+            .screen_to_ray(Vec2::new(600.0, 100.0), viewport)
+            .unwrap();
+        assert!((ray.direction - Vec3::unit_y()).magnitude() < 1e-5);
+
+        // A point above and right of the target is found up and right of the center.
+        let point = Point3::new(0.5, 0.0, 1.5);
+        let clip = camera.get_projection() * camera.get_view() * point.to_homogeneous();
+        let pixel = Vec2::new(
+            400.0 + (clip.x / clip.w + 1.0) * 200.0,
+            (clip.y / clip.w + 1.0) * 100.0,
+        );
+        assert!(pixel.x > 600.0 && pixel.y < 100.0);
+        let ray = camera.screen_to_ray(pixel, viewport).unwrap();
+        let to_point = point - ray.origin;
+        assert!(to_point.normalize().dot(ray.direction) > 1.0 - 1e-5);
+        assert!((ray.origin - Point3::new(0.0, -5.0, 1.0)).magnitude() < 0.2);
+    }
 }
@@ -0,0 +1,238 @@
+use std::time::Instant;
+
+/// The longest real time a single frame may advance a fixed timestep clock, so a stall does
+/// not queue up an unbounded number of updates.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// The shortest fixed timestep, shorter ones are raised to it.
+const MIN_TIMESTEP: f32 = 0.0001;
+
+/// The most fixed updates a single frame may run; the time left over is dropped.
+const MAX_TICKS_PER_FRAME: usize = 64;
+
+/// How a `Clock` advances the simulation time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockMode {
+    /// One update per frame, advanced by the real time since the previous frame.
+    RealTime,
+    /// Updates of exactly the given seconds, as many as the real time since the previous frame
+    /// allows. The rest is kept for the next frame and reported as the interpolation alpha.
+    ///
+    /// Steps below 0.1 ms are raised to it, and steps which are not positive fall back to
+    /// `RealTime`.
+    FixedTimestep(f32),
+    /// One update per frame, advanced only by `Clock::step`.
+    Manual,
+}
+
+/// A single update produced by a `Clock`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockTick {
+    /// The simulation time in seconds after this update.
+    pub time: f32,
+    /// The seconds advanced by this update.
+    pub delta: f32,
+    /// The index of this update, starting at 0.
+    pub frame: u64,
+}
+
+/// Drives the simulation time passed to `FeatherApp::on_update`.
+pub struct Clock {
+    mode: ClockMode,
+    start: Instant,
+    last: f32,
+    time: f32,
+    frame: u64,
+    accumulator: f32,
+    pending: f32,
+    alpha: f32,
+}
+
+impl Clock {
+    pub fn new(mode: ClockMode) -> Self {
+        Self {
+            mode: checked_mode(mode),
+            start: Instant::now(),
+            last: 0.0,
+            time: 0.0,
+            frame: 0,
+            accumulator: 0.0,
+            pending: 0.0,
+            alpha: 1.0,
+        }
+    }
+
+    pub fn get_mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ClockMode) -> &mut Self {
+        self.mode = checked_mode(mode);
+        self.accumulator = 0.0;
+        self.alpha = 1.0;
+        self
+    }
+
+    /// Returns the simulation time in seconds.
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    /// Sets the simulation time in seconds, without producing an update.
+    pub fn set_time(&mut self, time: f32) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// Returns the number of updates produced so far.
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns how far the rendered frame is between the last two fixed updates, from 0 to 1.
+    ///
+    /// This is always 1 outside of `ClockMode::FixedTimestep`.
+    pub fn get_alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// Advances the time of a manual clock; the next frame reports it as its delta.
+    pub fn step(&mut self, delta: f32) -> &mut Self {
+        self.time += delta;
+        self.pending += delta;
+        self
+    }
+
+    /// Advances the clock by the real time since the previous call and returns the updates to
+    /// run before rendering the next frame.
+    pub fn tick(&mut self) -> Vec<ClockTick> {
+        let now = self.start.elapsed().as_secs_f32();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.advance(elapsed)
+    }
+
+    /// Advances the clock by the given real seconds and returns the updates to run.
+    pub fn advance(&mut self, elapsed: f32) -> Vec<ClockTick> {
+        match self.mode {
+            ClockMode::RealTime => {
+                self.time += elapsed;
+                vec![self.next_tick(elapsed)]
+            }
+            ClockMode::FixedTimestep(step) => {
+                self.accumulator += elapsed.min(MAX_FRAME_TIME);
+                let mut ticks = Vec::new();
+                while self.accumulator >= step {
+                    if ticks.len() == MAX_TICKS_PER_FRAME {
+                        self.accumulator %= step;
+                        break;
+                    }
+                    self.accumulator -= step;
+                    self.time += step;
+                    ticks.push(self.next_tick(step));
+                }
+                self.alpha = self.accumulator / step;
+                ticks
+            }
+            ClockMode::Manual => {
+                let delta = std::mem::take(&mut self.pending);
+                vec![self.next_tick(delta)]
+            }
+        }
+    }
+
+    fn next_tick(&mut self, delta: f32) -> ClockTick {
+        let tick = ClockTick {
+            time: self.time,
+            delta,
+            frame: self.frame,
+        };
+        self.frame += 1;
+        tick
+    }
+}
+
+/// Replaces fixed timesteps the clock cannot run with usable ones.
+fn checked_mode(mode: ClockMode) -> ClockMode {
+    match mode {
+        ClockMode::FixedTimestep(step) if !(step > 0.0 && step.is_finite()) => {
+            log::warn!("Invalid fixed timestep {}, using real time instead.", step);
+            ClockMode::RealTime
+        }
+        ClockMode::FixedTimestep(step) if step < MIN_TIMESTEP => {
+            log::warn!(
+                "Fixed timestep {} is too short, using {}.",
+                step,
+                MIN_TIMESTEP
+            );
+            ClockMode::FixedTimestep(MIN_TIMESTEP)
+        }
+        mode => mode,
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(ClockMode::RealTime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_timestep() {
+        let mut clock = Clock::new(ClockMode::FixedTimestep(0.125));
+
+        assert!(clock.advance(0.0625).is_empty());
+        assert_eq!(clock.get_alpha(), 0.5);
+
+        let ticks = clock.advance(0.25);
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[1].time, 0.25);
+        assert_eq!(ticks[1].delta, 0.125);
+        assert_eq!(ticks[1].frame, 1);
+        assert_eq!(clock.get_alpha(), 0.5);
+
+        // Long stalls are clamped.
+        assert_eq!(clock.advance(10.0).len(), 2);
+        assert_eq!(clock.get_frame(), 4);
+    }
+
+    #[test]
+    fn test_invalid_timesteps() {
+        for step in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let mut clock = Clock::new(ClockMode::FixedTimestep(step));
+            assert_eq!(clock.get_mode(), ClockMode::RealTime);
+            assert_eq!(clock.advance(0.5).len(), 1);
+            clock.set_mode(ClockMode::FixedTimestep(step));
+            assert_eq!(clock.get_mode(), ClockMode::RealTime);
+        }
+
+        // Tiny steps are raised, and a frame runs a bounded number of them.
+        let mut clock = Clock::new(ClockMode::FixedTimestep(1e-9));
+        assert_eq!(clock.get_mode(), ClockMode::FixedTimestep(MIN_TIMESTEP));
+        assert_eq!(clock.advance(MAX_FRAME_TIME).len(), MAX_TICKS_PER_FRAME);
+        assert!(clock.get_alpha() < 1.0);
+    }
+
+    #[test]
+    fn test_manual() {
+        let mut clock = Clock::new(ClockMode::Manual);
+        clock.set_time(2.0).step(0.25);
+
+        let ticks = clock.advance(100.0);
+        assert_eq!(
+            ticks,
+            vec![ClockTick {
+                time: 2.25,
+                delta: 0.25,
+                frame: 0
+            }]
+        );
+        assert_eq!(clock.advance(1.0)[0].delta, 0.0);
+        assert_eq!(clock.get_time(), 2.25);
+        assert_eq!(clock.get_alpha(), 1.0);
+    }
+}
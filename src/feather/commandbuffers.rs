@@ -1,12 +1,53 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+
 use anyhow::Result;
+use cgmath::InnerSpace;
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
+use super::handle::Handle;
+use super::material::Material;
+use super::math::{Mat4, Vec4};
+use super::mesh::Mesh;
+use super::node::Node;
+use super::pipeline::{has_shader_pipelines, ShaderPipelines};
+use super::pushconstants::PushConstants;
+use super::scene::Scene;
+use super::vertexlayout::VertexLayout;
+use super::viewport::{get_scene_viewports, Viewport};
 
 //================================================
 // Command Buffers
 //================================================
 
+/// A single indexed draw of a mesh stored in a `MeshBuffer`.
+struct DrawCommand {
+    pipeline: vk::Pipeline,
+    material: vk::DescriptorSet,
+    vertex_buffer: vk::Buffer,
+    index_buffer: vk::Buffer,
+    index_count: u32,
+    first_index: u32,
+    vertex_offset: i32,
+    push_constants: PushConstants,
+}
+
+/// The state bound by previous draws, so unchanged state is not bound again.
+#[derive(Default)]
+struct BoundState {
+    pipeline: vk::Pipeline,
+    material: vk::DescriptorSet,
+    buffers: Option<(vk::Buffer, vk::Buffer)>,
+}
+
+/// The draws of a frame: opaque ones in scene order, transparent ones sorted back to front.
+#[derive(Default)]
+struct DrawList {
+    opaque: Vec<DrawCommand>,
+    transparent: Vec<(f32, DrawCommand)>,
+}
+
 pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Result<()> {
     // Allocate
 
@@ -22,71 +63,292 @@ pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Res
 
     data.command_buffers = device.allocate_command_buffers(&allocate_info)?;
 
+    Ok(())
+}
+
+/// Records the command buffer for the given swapchain image, drawing every node of every scene.
+pub unsafe fn update_command_buffer(
+    device: &Device,
+    data: &mut AppData,
+    image_index: usize,
+) -> Result<()> {
+    // Draws
+
+    let viewports = get_scene_viewports(data.app.as_mut());
+    let mut scene_draws = Vec::new();
+    for i in 0..data.app.get_num_scenes_to_render() {
+        let view = data.app.get_camera_to_render_scene(i).get_view();
+        let scene = data.app.get_scene_to_render(i);
+        scene.update_transforms();
+        let mut draws = DrawList::default();
+        collect_draw_commands(
+            scene,
+            view,
+            &data.pipelines,
+            &data.default_material,
+            &mut draws,
+        );
+        draws.transparent.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        scene_draws.push(draws);
+    }
+
     // Commands
 
-    for (i, command_buffer) in data.command_buffers.iter().enumerate() {
-        let info = vk::CommandBufferBeginInfo::builder();
+    let command_buffer = data.command_buffers[image_index];
 
-        device.begin_command_buffer(*command_buffer, &info)?;
+    device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())?;
 
-        let render_area = vk::Rect2D::builder()
-            .offset(vk::Offset2D::default())
-            .extent(data.swapchain.swapchain_extent);
+    let info = vk::CommandBufferBeginInfo::builder();
 
-        let color_clear_value = vk::ClearValue {
-            color: vk::ClearColorValue {
-                float32: [0.0, 0.0, 0.0, 1.0],
-            },
-        };
+    device.begin_command_buffer(command_buffer, &info)?;
 
-        let depth_clear_value = vk::ClearValue {
-            depth_stencil: vk::ClearDepthStencilValue {
-                depth: 1.0,
-                stencil: 0,
-            },
-        };
+    let render_area = vk::Rect2D::builder()
+        .offset(vk::Offset2D::default())
+        .extent(data.swapchain.swapchain_extent);
 
-        let clear_values = &[color_clear_value, depth_clear_value];
-        let info = vk::RenderPassBeginInfo::builder()
-            .render_pass(data.render_pass)
-            .framebuffer(data.framebuffers[i])
-            .render_area(render_area)
-            .clear_values(clear_values);
+    let color_clear_value = vk::ClearValue {
+        color: vk::ClearColorValue {
+            float32: [0.0, 0.0, 0.0, 1.0],
+        },
+    };
 
-        device.cmd_begin_render_pass(*command_buffer, &info, vk::SubpassContents::INLINE);
-        device.cmd_bind_pipeline(
-            *command_buffer,
+    let depth_clear_value = vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 1.0,
+            stencil: 0,
+        },
+    };
+
+    let clear_values = &[color_clear_value, depth_clear_value];
+    let info = vk::RenderPassBeginInfo::builder()
+        .render_pass(data.render_pass)
+        .framebuffer(data.framebuffers[image_index])
+        .render_area(render_area)
+        .clear_values(clear_values);
+
+    device.cmd_begin_render_pass(command_buffer, &info, vk::SubpassContents::INLINE);
+
+    let mut bound = BoundState::default();
+    for (i, draws) in scene_draws.iter().enumerate() {
+        // Each scene is drawn into its own viewport with the uniform buffer object of its own
+        // camera.
+        let rect = viewports[i].to_pixels(data.swapchain.swapchain_extent);
+        set_viewport(device, command_buffer, &viewports[i], rect);
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
             vk::PipelineBindPoint::GRAPHICS,
-            data.pipeline,
-        );
-        device.cmd_bind_vertex_buffers(
-            *command_buffer,
+            data.pipeline_layout,
             0,
-            &[data.mesh_buffer.vertex_buffer.unwrap()],
-            &[0],
+            &[data.descriptor_sets[image_index]],
+            &[(i as u64 * data.uniform_buffer_stride) as u32],
         );
-        device.cmd_bind_index_buffer(
-            *command_buffer,
-            data.mesh_buffer.index_buffer.unwrap(),
-            0,
-            vk::IndexType::UINT32,
+
+        for draw in &draws.opaque {
+            record_draw_command(device, data, command_buffer, draw, &mut bound);
+        }
+        for (_, draw) in &draws.transparent {
+            record_draw_command(device, data, command_buffer, draw, &mut bound);
+        }
+    }
+
+    device.cmd_end_render_pass(command_buffer);
+
+    device.end_command_buffer(command_buffer)?;
+
+    Ok(())
+}
+
+/// Sets the viewport and scissor to the rectangle and clears it as the viewport asks.
+unsafe fn set_viewport(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    viewport: &Viewport,
+    rect: vk::Rect2D,
+) {
+    let vk_viewport = vk::Viewport::builder()
+        .x(rect.offset.x as f32)
+        .y(rect.offset.y as f32)
+        .width(rect.extent.width as f32)
+        .height(rect.extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    device.cmd_set_viewport(command_buffer, 0, &[vk_viewport]);
+    device.cmd_set_scissor(command_buffer, 0, &[rect]);
+
+    let mut attachments = Vec::new();
+    if let Some(color) = viewport.clear_color {
+        attachments.push(
+            vk::ClearAttachment::builder()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .color_attachment(0)
+                .clear_value(vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: color.into(),
+                    },
+                })
+                .build(),
+        );
+    }
+    if viewport.clear_depth {
+        attachments.push(
+            vk::ClearAttachment::builder()
+                .aspect_mask(vk::ImageAspectFlags::DEPTH)
+                .clear_value(vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: 1.0,
+                        stencil: 0,
+                    },
+                })
+                .build(),
+        );
+    }
+
+    if !attachments.is_empty() {
+        let clear_rect = vk::ClearRect::builder()
+            .rect(rect)
+            .base_array_layer(0)
+            .layer_count(1);
+        device.cmd_clear_attachments(command_buffer, &attachments, &[clear_rect]);
+    }
+}
+
+unsafe fn record_draw_command(
+    device: &Device,
+    data: &AppData,
+    command_buffer: vk::CommandBuffer,
+    draw: &DrawCommand,
+    bound: &mut BoundState,
+) {
+    if bound.pipeline != draw.pipeline {
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            draw.pipeline,
         );
+        bound.pipeline = draw.pipeline;
+    }
+
+    if bound.material != draw.material {
         device.cmd_bind_descriptor_sets(
-            *command_buffer,
+            command_buffer,
             vk::PipelineBindPoint::GRAPHICS,
             data.pipeline_layout,
-            0,
-            &[data.descriptor_sets[i]],
+            1,
+            &[draw.material],
             &[],
         );
+        bound.material = draw.material;
+    }
+
+    if bound.buffers != Some((draw.vertex_buffer, draw.index_buffer)) {
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[draw.vertex_buffer], &[0]);
+        device.cmd_bind_index_buffer(command_buffer, draw.index_buffer, 0, vk::IndexType::UINT32);
+        bound.buffers = Some((draw.vertex_buffer, draw.index_buffer));
+    }
+
+    let push_constants_bytes = std::slice::from_raw_parts(
+        &draw.push_constants as *const PushConstants as *const u8,
+        size_of::<PushConstants>(),
+    );
+
+    device.cmd_push_constants(
+        command_buffer,
+        data.pipeline_layout,
+        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        0,
+        push_constants_bytes,
+    );
+
+    device.cmd_draw_indexed(
+        command_buffer,
+        draw.index_count,
+        1,
+        draw.first_index,
+        draw.vertex_offset,
+        0,
+    );
+}
+
+fn collect_draw_commands(
+    scene: &Scene,
+    view: Mat4,
+    pipelines: &HashMap<String, HashMap<VertexLayout, ShaderPipelines>>,
+    default_material: &Material,
+    draws: &mut DrawList,
+) {
+    for (node_handle, _, world_transform) in scene.iter_depth_first().visible_only() {
+        let Some(node) = scene.get_node(node_handle) else {
+            continue;
+        };
 
-        let mesh = data.scene.get_mesh(data.mesh).unwrap();
+        let Some(mesh_handle) = node.get_mesh() else {
+            continue;
+        };
+        let Some(layout) = scene.get_mesh(mesh_handle).map(|m| m.get_layout()) else {
+            continue;
+        };
 
-        device.cmd_draw_indexed(*command_buffer, mesh.indices.len() as u32, 1, 0, 0, 0);
-        device.cmd_end_render_pass(*command_buffer);
+        let model = node.get_global_transform().unwrap_or(world_transform);
+        let material =
+            get_node_material(scene, node, layout, pipelines).unwrap_or(default_material);
+        let Some(mut draw) = get_draw_command(scene, mesh_handle, material, model) else {
+            continue;
+        };
+        let Some(shader_pipelines) = pipelines
+            .get(material.get_shader().get_name())
+            .and_then(|p| p.get(layout))
+        else {
+            continue;
+        };
 
-        device.end_command_buffer(*command_buffer)?;
+        if node.is_transparent() {
+            let distance = (view * model * Vec4::new(0.0, 0.0, 0.0, 1.0))
+                .truncate()
+                .magnitude();
+            draw.pipeline = shader_pipelines.transparent;
+            draws.transparent.push((distance, draw));
+        } else {
+            draw.pipeline = shader_pipelines.opaque;
+            draws.opaque.push(draw);
+        }
     }
+}
 
-    Ok(())
+/// Returns the material of the node if it is ready to draw meshes with the given layout.
+fn get_node_material<'a>(
+    scene: &'a Scene,
+    node: &Node,
+    layout: &VertexLayout,
+    pipelines: &HashMap<String, HashMap<VertexLayout, ShaderPipelines>>,
+) -> Option<&'a Material> {
+    let material = scene.get_material(node.get_material()?)?;
+    let ready = material.get_descriptor_set().is_some()
+        && has_shader_pipelines(pipelines, material.get_shader(), layout);
+    ready.then_some(material)
+}
+
+fn get_draw_command(
+    scene: &Scene,
+    mesh_handle: Handle<Mesh>,
+    material: &Material,
+    model: Mat4,
+) -> Option<DrawCommand> {
+    let mesh = scene.get_mesh(mesh_handle)?;
+    let buffer_data = mesh.mesh_buffer_data.as_ref()?;
+    let mesh_buffer = scene.buffers.get(buffer_data.buffer_handle)?;
+
+    Some(DrawCommand {
+        pipeline: vk::Pipeline::default(),
+        material: material.get_descriptor_set()?,
+        vertex_buffer: mesh_buffer.vertex_buffer?,
+        index_buffer: mesh_buffer.index_buffer?,
+        index_count: buffer_data.index_size as u32,
+        first_index: buffer_data.index_begin_index as u32,
+        vertex_offset: buffer_data.vertex_begin_index as i32,
+        push_constants: PushConstants {
+            model,
+            base_color: material.get_base_color(),
+        },
+    })
 }
@@ -1,14 +1,37 @@
 use crate::feather::camera::Camera;
+use crate::feather::inputevent::InputEvent;
+use crate::feather::inputstate::InputState;
 use crate::feather::scene::Scene;
+use crate::feather::viewport::Viewport;
 use anyhow::Result;
 
 pub trait FeatherApp {
     fn on_create(&mut self) -> Result<()>;
-    fn on_render(&mut self) -> Result<()>;
-    fn on_update(&mut self, time: f32) -> Result<()>;
+    /// Called once before every rendered frame, after the updates of the frame.
+    ///
+    /// `alpha` is how far the frame is between the last two updates of a fixed timestep clock,
+    /// for interpolating what is drawn; it is 1 for the other clock modes.
+    fn on_render(&mut self, alpha: f32) -> Result<()>;
+    /// Called for every update of the app's `Clock`, with the simulation time, the seconds
+    /// since the previous update and the state of the keyboard and mouse.
+    fn on_update(&mut self, time: f32, delta: f32, frame: u64, input: &InputState) -> Result<()>;
+    /// Called for every input event, before the updates of the next frame.
+    fn on_input(&mut self, _event: &InputEvent) -> Result<()> {
+        Ok(())
+    }
     fn on_destroy(&mut self);
 
     fn get_num_scenes_to_render(&self) -> usize;
     fn get_scene_to_render(&mut self, scene_index: usize) -> &mut Scene;
     fn get_camera_to_render_scene(&mut self, scene_index: usize) -> &mut dyn Camera;
+    /// Returns the part of the screen the scene is rendered into. Scenes without a viewport
+    /// are laid out in a grid over all scenes.
+    fn get_viewport_to_render_scene(&mut self, _scene_index: usize) -> Option<Viewport> {
+        None
+    }
+
+    /// Returns a PNG path when the next rendered frame should be captured to it.
+    fn take_capture_request(&mut self) -> Option<String> {
+        None
+    }
 }
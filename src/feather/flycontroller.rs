@@ -0,0 +1,182 @@
+use cgmath::{Angle, Deg, InnerSpace};
+
+use super::camera::Camera;
+use super::inputevent::{Key, MouseButton};
+use super::inputstate::InputState;
+use super::math::{Point3, Vec3};
+
+/// Flies a camera first-person style, with Z as the up axis.
+///
+/// W/S move forward and back, A/D strafe, E/Q move up and down along Z and Shift speeds up.
+/// Moving the mouse looks around, by default only while the right button is held.
+pub struct FlyController {
+    position: Point3,
+    yaw: f32,
+    pitch: f32,
+    speed: f32,
+    fast_factor: f32,
+    look_speed: f32,
+    look_button: Option<MouseButton>,
+}
+
+impl FlyController {
+    pub fn new(position: Point3) -> Self {
+        Self {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 2.0,
+            fast_factor: 4.0,
+            look_speed: 0.2,
+            look_button: Some(MouseButton::Right),
+        }
+    }
+
+    pub fn get_position(&self) -> Point3 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Point3) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    /// Returns the heading around the Z axis in degrees, 0 looking along +X.
+    pub fn get_yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// Returns the angle above the XY plane in degrees.
+    pub fn get_pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    pub fn set_yaw_pitch(&mut self, yaw: f32, pitch: f32) -> &mut Self {
+        self.yaw = yaw.rem_euclid(360.0);
+        self.pitch = pitch.clamp(-89.0, 89.0);
+        self
+    }
+
+    /// Turns the camera towards a point.
+    pub fn look_at(&mut self, target: Point3) -> &mut Self {
+        let direction = target - self.position;
+        let distance = direction.magnitude();
+        if distance > 0.0 {
+            let yaw = direction.y.atan2(direction.x).to_degrees();
+            let pitch = (direction.z / distance).asin().to_degrees();
+            self.set_yaw_pitch(yaw, pitch);
+        }
+        self
+    }
+
+    /// Sets the units moved per second and the factor applied while Shift is held.
+    pub fn set_speed(&mut self, speed: f32, fast_factor: f32) -> &mut Self {
+        self.speed = speed;
+        self.fast_factor = fast_factor;
+        self
+    }
+
+    /// Sets the degrees turned per pixel of mouse movement.
+    pub fn set_look_speed(&mut self, look_speed: f32) -> &mut Self {
+        self.look_speed = look_speed;
+        self
+    }
+
+    /// Sets the button which has to be held to look around, or `None` to always follow the
+    /// mouse.
+    pub fn set_look_button(&mut self, look_button: Option<MouseButton>) -> &mut Self {
+        self.look_button = look_button;
+        self
+    }
+
+    pub fn get_forward(&self) -> Vec3 {
+        let (yaw, pitch) = (Deg(self.yaw), Deg(self.pitch));
+        Vec3::new(
+            pitch.cos() * yaw.cos(),
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+        )
+    }
+
+    /// Returns the horizontal direction to the right of the camera.
+    pub fn get_right(&self) -> Vec3 {
+        let yaw = Deg(self.yaw);
+        Vec3::new(yaw.sin(), -yaw.cos(), 0.0)
+    }
+
+    /// Moves and turns from the input of the current frame.
+    pub fn update(&mut self, input: &InputState, delta: f32) -> &mut Self {
+        if self.look_button.is_none_or(|b| input.is_button_down(b)) {
+            let mouse = input.get_mouse_delta();
+            self.set_yaw_pitch(
+                self.yaw - mouse.x * self.look_speed,
+                self.pitch - mouse.y * self.look_speed,
+            );
+        }
+
+        let axis = |negative: Key, positive: Key| {
+            input.is_key_down(positive) as i32 as f32 - input.is_key_down(negative) as i32 as f32
+        };
+        let direction = self.get_forward() * axis(Key::S, Key::W)
+            + self.get_right() * axis(Key::A, Key::D)
+            + Vec3::unit_z() * axis(Key::Q, Key::E);
+        if direction.magnitude2() > 0.0 {
+            let mut speed = self.speed;
+            if input.is_key_down(Key::Shift) {
+                speed *= self.fast_factor;
+            }
+            self.position += direction.normalize() * speed * delta;
+        }
+        self
+    }
+
+    /// Points the camera along the current heading.
+    pub fn apply(&self, camera: &mut dyn Camera) {
+        camera.set_view(
+            self.position,
+            self.position + self.get_forward(),
+            Vec3::unit_z(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::camera::Camera;
+    use crate::feather::inputevent::InputEvent;
+    use crate::feather::math::Mat4;
+    use crate::feather::perspectivecamera::PerspectiveCamera;
+
+    #[test]
+    fn test_fly() {
+        let mut fly = FlyController::new(Point3::new(0.0, 0.0, 1.0));
+        fly.look_at(Point3::new(0.0, 5.0, 1.0)).set_speed(2.0, 4.0);
+        assert!((fly.get_yaw() - 90.0).abs() < 1e-4);
+
+        let mut input = InputState::new();
+        input.handle_event(&InputEvent::KeyPressed(Key::W));
+        input.handle_event(&InputEvent::KeyPressed(Key::D));
+        fly.update(&input, 0.5);
+        let expected = Point3::new(1.0, 1.0, 0.0) * (1.0 / 2f32.sqrt()) + Vec3::unit_z();
+        assert!((fly.get_position() - expected).magnitude() < 1e-4);
+
+        // Looking needs the look button.
+        input.handle_event(&InputEvent::MouseMoved { x: 0.0, y: 0.0 });
+        input.handle_event(&InputEvent::MouseMoved { x: 50.0, y: 0.0 });
+        fly.update(&input, 0.0);
+        assert!((fly.get_yaw() - 90.0).abs() < 1e-4);
+        input.handle_event(&InputEvent::MouseButtonPressed(MouseButton::Right));
+        fly.update(&input, 0.0);
+        assert!((fly.get_yaw() - 80.0).abs() < 1e-4);
+
+        let mut camera = PerspectiveCamera::new();
+        fly.apply(&mut camera);
+        let view = Mat4::look_at_rh(
+            fly.get_position(),
+            fly.get_position() + fly.get_forward(),
+            Vec3::unit_z(),
+        );
+        assert_eq!(camera.get_view(), view);
+    }
+}
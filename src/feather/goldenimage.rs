@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::app::App;
+use super::clock::ClockMode;
+use super::featherapp::FeatherApp;
+use super::rgbaimage::RgbaImage;
+use super::textureloader::TextureError;
+
+/// Set this environment variable to write the rendered frames as the new golden images.
+pub const UPDATE_GOLDENS_ENV: &str = "FEATHER_UPDATE_GOLDENS";
+
+/// The result of comparing a rendered image against its reference.
+pub struct ImageDiff {
+    pub mismatched: usize,
+    pub total: usize,
+    /// Mismatched pixels in red over a dimmed copy of the reference.
+    pub image: RgbaImage,
+}
+
+impl ImageDiff {
+    pub fn get_mismatch_percent(&self) -> f32 {
+        self.mismatched as f32 * 100.0 / self.total.max(1) as f32
+    }
+}
+
+/// Compares two images of the same size.
+///
+/// A pixel matches when none of its channels differs by more than `tolerance`.
+pub fn compare_images(
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+    tolerance: u8,
+) -> Result<ImageDiff> {
+    let (width, height) = (expected.get_width(), expected.get_height());
+    if (actual.get_width(), actual.get_height()) != (width, height) {
+        return Err(anyhow!(
+            "Image size {}x{} does not match the reference size {}x{}.",
+            actual.get_width(),
+            actual.get_height(),
+            width,
+            height
+        ));
+    }
+
+    let mut mismatched = 0;
+    let mut pixels = Vec::with_capacity(expected.get_pixels().len());
+    let pairs = actual
+        .get_pixels()
+        .chunks_exact(4)
+        .zip(expected.get_pixels().chunks_exact(4));
+    for (a, e) in pairs {
+        if a.iter().zip(e).any(|(a, e)| a.abs_diff(*e) > tolerance) {
+            mismatched += 1;
+            pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 12) as u8;
+            pixels.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    Ok(ImageDiff {
+        mismatched,
+        total: width as usize * height as usize,
+        image: RgbaImage::new(width, height, pixels)?,
+    })
+}
+
+/// Renders a `FeatherApp` headless and compares the frame with a checked-in reference PNG.
+///
+/// The app is rendered for a fixed number of frames at a fixed time, so the result does not
+/// depend on how fast the machine is. When the comparison fails, `<name>.actual.png` and
+/// `<name>.diff.png` are written next to the reference.
+pub struct GoldenImage {
+    path: String,
+    width: u32,
+    height: u32,
+    frames: u32,
+    time: f32,
+    tolerance: u8,
+    max_mismatch_percent: f32,
+}
+
+impl GoldenImage {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            width: 256,
+            height: 256,
+            frames: 1,
+            time: 0.0,
+            tolerance: 2,
+            max_mismatch_percent: 0.1,
+        }
+    }
+
+    pub fn set_size(&mut self, width: u32, height: u32) -> &mut Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn set_frames(&mut self, frames: u32) -> &mut Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Sets the time in seconds passed to the app for every frame.
+    pub fn set_time(&mut self, time: f32) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// Sets the largest per-channel difference for which a pixel still matches.
+    pub fn set_tolerance(&mut self, tolerance: u8) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn set_max_mismatch_percent(&mut self, max_mismatch_percent: f32) -> &mut Self {
+        self.max_mismatch_percent = max_mismatch_percent;
+        self
+    }
+
+    /// Renders the app offscreen and returns the last frame.
+    pub unsafe fn render(&self, app: Box<dyn FeatherApp>) -> Result<RgbaImage> {
+        let mut app = App::create_headless(app, self.width, self.height)?;
+        app.get_clock_mut()
+            .set_mode(ClockMode::Manual)
+            .set_time(self.time);
+        let image = (0..self.frames)
+            .try_for_each(|_| app.render_offscreen())
+            .and_then(|_| app.read_offscreen_image());
+        app.destroy();
+        image
+    }
+
+    /// Renders the app and compares the frame with the reference.
+    pub unsafe fn check_app(&self, app: Box<dyn FeatherApp>) -> Result<()> {
+        self.check(&self.render(app)?)
+    }
+
+    /// Compares an image with the reference, or replaces the reference when the
+    /// `FEATHER_UPDATE_GOLDENS` environment variable is set.
+    pub fn check(&self, image: &RgbaImage) -> Result<()> {
+        let update = std::env::var(UPDATE_GOLDENS_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        self.check_or_update(image, update)
+    }
+
+    fn check_or_update(&self, image: &RgbaImage, update: bool) -> Result<()> {
+        let path = Path::new(&self.path);
+        if update {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            image.save_png(&self.path)?;
+            return Ok(());
+        }
+
+        let expected = match RgbaImage::load_png(&self.path) {
+            Err(TextureError::Io(_, e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow!(
+                    "Golden image `{}` does not exist, run with {}=1 to create it.",
+                    self.path,
+                    UPDATE_GOLDENS_ENV
+                ));
+            }
+            result => result?,
+        };
+
+        let diff = compare_images(image, &expected, self.tolerance)?;
+        if diff.get_mismatch_percent() <= self.max_mismatch_percent {
+            return Ok(());
+        }
+
+        let actual_path = path.with_extension("actual.png");
+        let diff_path = path.with_extension("diff.png");
+        image.save_png(&actual_path.to_string_lossy())?;
+        diff.image.save_png(&diff_path.to_string_lossy())?;
+        Err(anyhow!(
+            "{:.2}% of the pixels differ from golden image `{}` (at most {:.2}% allowed), see `{}`.",
+            diff.get_mismatch_percent(),
+            self.path,
+            self.max_mismatch_percent,
+            diff_path.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> RgbaImage {
+        RgbaImage::new(pixels.len() as u32, 1, pixels.concat()).unwrap()
+    }
+
+    #[test]
+    fn test_compare_images() {
+        let expected = image(&[[100, 100, 100, 255], [0, 0, 0, 255]]);
+        let actual = image(&[[102, 98, 100, 255], [0, 0, 3, 255]]);
+
+        let diff = compare_images(&actual, &expected, 2).unwrap();
+        assert_eq!((diff.mismatched, diff.total), (1, 2));
+        assert_eq!(diff.get_mismatch_percent(), 50.0);
+        assert_eq!(diff.image.get_pixel(0, 0), [25, 25, 25, 255]);
+        assert_eq!(diff.image.get_pixel(1, 0), [255, 0, 0, 255]);
+
+        assert_eq!(compare_images(&actual, &expected, 3).unwrap().mismatched, 0);
+        assert!(compare_images(&image(&[[0; 4]]), &expected, 0).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = std::env::temp_dir().join("feather_test_golden");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("golden.png");
+        let golden = GoldenImage::new(path.to_str().unwrap());
+
+        let expected = image(&[[10, 20, 30, 255], [40, 50, 60, 255]]);
+        assert!(golden.check_or_update(&expected, false).is_err());
+        golden.check_or_update(&expected, true).unwrap();
+        golden.check_or_update(&expected, false).unwrap();
+
+        let actual = image(&[[10, 20, 30, 255], [90, 50, 60, 255]]);
+        assert!(golden.check_or_update(&actual, false).is_err());
+        assert!(dir.join("golden.diff.png").exists());
+        assert!(dir.join("golden.actual.png").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// A typed reference to an object stored in an `ObjDB`.
+///
+/// Besides the slot index a handle carries the generation of the slot and the id of the
+/// database which created it, so a handle to a removed object (or to an object of another
+/// database) is never resolved to whatever object reuses the slot later.
+pub struct Handle<T> {
+    db_id: u32,
+    index: usize,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(db_id: u32, index: usize, generation: u32) -> Self {
+        Self {
+            db_id,
+            index,
+            generation,
+            _marker: PhantomData,
+        }
+    }
+
+    /// A handle which does not point to any object.
+    pub fn invalid() -> Self {
+        Self::new(0, usize::MAX, 0)
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        self.db_id == 0
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub(crate) fn get_db_id(&self) -> u32 {
+        self.db_id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.db_id == other.db_id
+            && self.index == other.index
+            && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.db_id.hash(state);
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> Default for Handle<T> {
+    fn default() -> Self {
+        Self::invalid()
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_invalid() {
+            write!(f, "Handle(invalid)")
+        } else {
+            write!(f, "Handle({}v{})", self.index, self.generation)
+        }
+    }
+}
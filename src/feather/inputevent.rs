@@ -0,0 +1,135 @@
+use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// The pixels of a pixel based scroll delta counted as one line.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// A key, named after its position on a US keyboard so WASD style bindings do not depend on
+/// the keyboard layout.
+#[rustfmt::skip]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Left, Right, Up, Down,
+    Space, Enter, Escape, Tab, Backspace, Delete, Insert, Home, End, PageUp, PageDown,
+    Shift, Control, Alt, Super,
+    Minus, Equal, Comma, Period, Slash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+/// An input event, independent of the windowing library.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(Key),
+    KeyReleased(Key),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    /// The cursor moved to a position in physical pixels from the top left of the window.
+    MouseMoved {
+        x: f32,
+        y: f32,
+    },
+    /// The wheel was scrolled by the given lines; positive y scrolls up.
+    MouseWheel {
+        x: f32,
+        y: f32,
+    },
+    /// The window lost the focus, so no release events arrive for held keys and buttons.
+    FocusLost,
+}
+
+impl InputEvent {
+    /// Converts a winit window event, returning `None` for events which are not input or keys
+    /// without a `Key`.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let key = match event.physical_key {
+                    PhysicalKey::Code(code) => key_from_code(code)?,
+                    PhysicalKey::Unidentified(_) => return None,
+                };
+                match event.state {
+                    ElementState::Pressed => Some(Self::KeyPressed(key)),
+                    ElementState::Released => Some(Self::KeyReleased(key)),
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    winit::event::MouseButton::Left => MouseButton::Left,
+                    winit::event::MouseButton::Right => MouseButton::Right,
+                    winit::event::MouseButton::Middle => MouseButton::Middle,
+                    winit::event::MouseButton::Back => MouseButton::Other(3),
+                    winit::event::MouseButton::Forward => MouseButton::Other(4),
+                    winit::event::MouseButton::Other(b) => MouseButton::Other(*b),
+                };
+                match state {
+                    ElementState::Pressed => Some(Self::MouseButtonPressed(button)),
+                    ElementState::Released => Some(Self::MouseButtonReleased(button)),
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => Some(Self::MouseMoved {
+                x: position.x as f32,
+                y: position.y as f32,
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    MouseScrollDelta::PixelDelta(p) => {
+                        (p.x as f32 / PIXELS_PER_LINE, p.y as f32 / PIXELS_PER_LINE)
+                    }
+                };
+                Some(Self::MouseWheel { x, y })
+            }
+            WindowEvent::Focused(false) => Some(Self::FocusLost),
+            _ => None,
+        }
+    }
+}
+
+#[rustfmt::skip]
+fn key_from_code(code: KeyCode) -> Option<Key> {
+    Some(match code {
+        KeyCode::KeyA => Key::A, KeyCode::KeyB => Key::B, KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D, KeyCode::KeyE => Key::E, KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G, KeyCode::KeyH => Key::H, KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J, KeyCode::KeyK => Key::K, KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M, KeyCode::KeyN => Key::N, KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P, KeyCode::KeyQ => Key::Q, KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S, KeyCode::KeyT => Key::T, KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V, KeyCode::KeyW => Key::W, KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y, KeyCode::KeyZ => Key::Z,
+        KeyCode::Digit0 => Key::Digit0, KeyCode::Digit1 => Key::Digit1,
+        KeyCode::Digit2 => Key::Digit2, KeyCode::Digit3 => Key::Digit3,
+        KeyCode::Digit4 => Key::Digit4, KeyCode::Digit5 => Key::Digit5,
+        KeyCode::Digit6 => Key::Digit6, KeyCode::Digit7 => Key::Digit7,
+        KeyCode::Digit8 => Key::Digit8, KeyCode::Digit9 => Key::Digit9,
+        KeyCode::F1 => Key::F1, KeyCode::F2 => Key::F2, KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4, KeyCode::F5 => Key::F5, KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7, KeyCode::F8 => Key::F8, KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10, KeyCode::F11 => Key::F11, KeyCode::F12 => Key::F12,
+        KeyCode::ArrowLeft => Key::Left, KeyCode::ArrowRight => Key::Right,
+        KeyCode::ArrowUp => Key::Up, KeyCode::ArrowDown => Key::Down,
+        KeyCode::Space => Key::Space, KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape, KeyCode::Tab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace, KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert, KeyCode::Home => Key::Home, KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp, KeyCode::PageDown => Key::PageDown,
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => Key::Shift,
+        KeyCode::ControlLeft | KeyCode::ControlRight => Key::Control,
+        KeyCode::AltLeft | KeyCode::AltRight => Key::Alt,
+        KeyCode::SuperLeft | KeyCode::SuperRight => Key::Super,
+        KeyCode::Minus => Key::Minus, KeyCode::Equal => Key::Equal,
+        KeyCode::Comma => Key::Comma, KeyCode::Period => Key::Period,
+        KeyCode::Slash => Key::Slash,
+        _ => return None,
+    })
+}
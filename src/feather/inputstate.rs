@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use super::inputevent::{InputEvent, Key, MouseButton};
+use super::math::Vec2;
+
+/// A snapshot of the keyboard and mouse, built from `InputEvent`s.
+///
+/// Deltas and the keys and buttons pressed are collected between two updates, so each of them
+/// is seen by exactly one `FeatherApp::on_update`.
+#[derive(Clone, Debug)]
+pub struct InputState {
+    keys_down: HashSet<Key>,
+    keys_pressed: HashSet<Key>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    mouse_position: Option<Vec2>,
+    mouse_delta: Vec2,
+    wheel_delta: Vec2,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self {
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            mouse_position: None,
+            mouse_delta: Vec2::new(0.0, 0.0),
+            wheel_delta: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::KeyPressed(key) => {
+                // Held keys repeat their pressed events.
+                if self.keys_down.insert(key) {
+                    self.keys_pressed.insert(key);
+                }
+            }
+            InputEvent::KeyReleased(key) => {
+                self.keys_down.remove(&key);
+            }
+            InputEvent::MouseButtonPressed(button) => {
+                self.buttons_down.insert(button);
+                self.buttons_pressed.insert(button);
+            }
+            InputEvent::MouseButtonReleased(button) => {
+                self.buttons_down.remove(&button);
+            }
+            InputEvent::MouseMoved { x, y } => {
+                let position = Vec2::new(x, y);
+                if let Some(previous) = self.mouse_position {
+                    self.mouse_delta += position - previous;
+                }
+                self.mouse_position = Some(position);
+            }
+            InputEvent::MouseWheel { x, y } => {
+                self.wheel_delta += Vec2::new(x, y);
+            }
+            InputEvent::FocusLost => {
+                self.keys_down.clear();
+                self.buttons_down.clear();
+            }
+        }
+    }
+
+    /// Starts collecting the deltas and presses of the next frame.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.buttons_pressed.clear();
+        self.mouse_delta = Vec2::new(0.0, 0.0);
+        self.wheel_delta = Vec2::new(0.0, 0.0);
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Returns whether the key went down during the current frame.
+    pub fn was_key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// Returns whether the button went down during the current frame.
+    pub fn was_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Returns the cursor position in physical pixels, or `None` before it first moved.
+    pub fn get_mouse_position(&self) -> Option<Vec2> {
+        self.mouse_position
+    }
+
+    /// Returns how far the cursor moved during the current frame.
+    pub fn get_mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Returns the lines scrolled during the current frame.
+    pub fn get_wheel_delta(&self) -> Vec2 {
+        self.wheel_delta
+    }
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_events() {
+        let mut input = InputState::new();
+        let events = [
+            InputEvent::KeyPressed(Key::W),
+            InputEvent::KeyPressed(Key::W),
+            InputEvent::MouseMoved { x: 10.0, y: 10.0 },
+            InputEvent::MouseMoved { x: 15.0, y: 8.0 },
+            InputEvent::MouseButtonPressed(MouseButton::Left),
+            InputEvent::MouseWheel { x: 0.0, y: 1.5 },
+        ];
+        events.iter().for_each(|e| input.handle_event(e));
+
+        assert!(input.is_key_down(Key::W) && input.was_key_pressed(Key::W));
+        assert!(!input.is_key_down(Key::S));
+        assert!(input.is_button_down(MouseButton::Left));
+        assert_eq!(input.get_mouse_position(), Some(Vec2::new(15.0, 8.0)));
+        assert_eq!(input.get_mouse_delta(), Vec2::new(5.0, -2.0));
+        assert_eq!(input.get_wheel_delta(), Vec2::new(0.0, 1.5));
+
+        input.end_frame();
+        assert!(input.is_key_down(Key::W) && !input.was_key_pressed(Key::W));
+        assert_eq!(input.get_mouse_delta(), Vec2::new(0.0, 0.0));
+
+        input.handle_event(&InputEvent::KeyReleased(Key::W));
+        input.handle_event(&InputEvent::FocusLost);
+        assert!(!input.is_key_down(Key::W));
+        assert!(!input.is_button_down(MouseButton::Left));
+    }
+}
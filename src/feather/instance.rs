@@ -26,8 +26,9 @@ const PORTABILITY_MACOS_VERSION: Version = Version::new(1, 3, 216);
 // Instance
 //================================================
 
+/// Creates the instance; without a window no surface extensions are enabled.
 pub unsafe fn create_instance(
-    window: &Window,
+    window: Option<&Window>,
     entry: &Entry,
     data: &mut AppData,
 ) -> Result<Instance> {
@@ -48,11 +49,17 @@ pub unsafe fn create_instance(
         .map(|l| l.layer_name)
         .collect::<HashSet<_>>();
 
+    data.validation = VALIDATION_ENABLED;
     if VALIDATION_ENABLED && !available_layers.contains(&VALIDATION_LAYER) {
-        return Err(anyhow!("Validation layer requested but not supported."));
+        // Build machines rendering headless often have no validation layers installed.
+        if !data.headless {
+            return Err(anyhow!("Validation layer requested but not supported."));
+        }
+        warn!("Validation layer requested but not supported, continuing without it.");
+        data.validation = false;
     }
 
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.validation {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         Vec::new()
@@ -60,10 +67,13 @@ pub unsafe fn create_instance(
 
     // Extensions
 
-    let mut extensions = vk_window::get_required_instance_extensions(window)
-        .iter()
-        .map(|e| e.as_ptr())
-        .collect::<Vec<_>>();
+    let mut extensions = match window {
+        Some(window) => vk_window::get_required_instance_extensions(window)
+            .iter()
+            .map(|e| e.as_ptr())
+            .collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
 
     // Required by Vulkan SDK on macOS since 1.3.216.
     let flags = if cfg!(target_os = "macos") && entry.version()? >= PORTABILITY_MACOS_VERSION {
@@ -79,7 +89,7 @@ pub unsafe fn create_instance(
         vk::InstanceCreateFlags::empty()
     };
 
-    if VALIDATION_ENABLED {
+    if data.validation {
         extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION.name.as_ptr());
     }
 
@@ -100,7 +110,7 @@ pub unsafe fn create_instance(
         )
         .user_callback(Some(debug_callback));
 
-    if VALIDATION_ENABLED {
+    if data.validation {
         info = info.push_next(&mut debug_info);
     }
 
@@ -108,7 +118,7 @@ pub unsafe fn create_instance(
 
     // Messenger
 
-    if VALIDATION_ENABLED {
+    if data.validation {
         data.messenger = instance.create_debug_utils_messenger_ext(&debug_info, None)?;
     }
 
@@ -7,8 +7,6 @@ use vulkanalia::Version;
 use super::appdata::AppData;
 use super::queuefamilyindices::QueueFamilyIndices;
 
-/// Whether the validation layers should be enabled.
-const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
 /// The name of the validation layers.
 const VALIDATION_LAYER: vk::ExtensionName =
     vk::ExtensionName::from_bytes(b"VK_LAYER_KHRONOS_validation");
@@ -47,7 +45,7 @@ pub unsafe fn create_logical_device(
 
     // Layers
 
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.validation {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         vec![]
@@ -55,10 +53,13 @@ pub unsafe fn create_logical_device(
 
     // Extensions
 
-    let mut extensions = DEVICE_EXTENSIONS
-        .iter()
-        .map(|n| n.as_ptr())
-        .collect::<Vec<_>>();
+    let mut extensions = match data.headless {
+        true => Vec::new(),
+        false => DEVICE_EXTENSIONS
+            .iter()
+            .map(|n| n.as_ptr())
+            .collect::<Vec<_>>(),
+    };
 
     // Required by Vulkan SDK on macOS since 1.3.216.
     if cfg!(target_os = "macos") && entry.version()? >= PORTABILITY_MACOS_VERSION {
@@ -1,7 +1,219 @@
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+use super::atlas::Atlas;
+use super::handle::Handle;
+use super::math::Vec4;
+use super::object::Object;
+use super::samplersettings::SamplerSettings;
 use super::shader::Shader;
 use super::texture::Texture;
 
+/// Describes how the surface of a node is drawn.
+///
+/// The base color multiplies the texture color; a material without a texture is drawn with
+/// the base color alone.
 pub struct Material {
+    name: Option<String>,
+    handle: Handle<Material>,
+    base_color: Vec4,
     shader: Shader,
     texture: Option<Texture>,
+    sampler_settings: SamplerSettings,
+    needs_prepare: bool,
+    sampler: Option<vk::Sampler>,
+    descriptor_pool: Option<vk::DescriptorPool>,
+    descriptor_set: Option<vk::DescriptorSet>,
+}
+
+impl Object for Material {
+    fn get_name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name)
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn set_handle(&mut self, handle: Handle<Self>) {
+        self.handle = handle;
+    }
+
+    fn get_handle(&self) -> Handle<Self> {
+        self.handle
+    }
+}
+
+impl Material {
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            name,
+            handle: Handle::invalid(),
+            base_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            shader: Shader::default(),
+            texture: None,
+            sampler_settings: SamplerSettings::default(),
+            needs_prepare: true,
+            sampler: None,
+            descriptor_pool: None,
+            descriptor_set: None,
+        }
+    }
+
+    pub fn with_texture(name: Option<String>, texture: Texture) -> Self {
+        let mut material = Self::new(name);
+        material.set_texture(Some(texture));
+        material
+    }
+
+    pub fn set_base_color(&mut self, base_color: Vec4) -> &mut Self {
+        self.base_color = base_color;
+        self
+    }
+
+    pub fn get_base_color(&self) -> Vec4 {
+        self.base_color
+    }
+
+    pub fn set_shader(&mut self, shader: Shader) -> &mut Self {
+        self.shader = shader;
+        self
+    }
+
+    pub fn get_shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    pub fn set_texture(&mut self, texture: Option<Texture>) -> &mut Self {
+        self.texture = texture;
+        self.needs_prepare = true;
+        self
+    }
+
+    pub fn get_texture(&self) -> Option<Texture> {
+        self.texture
+    }
+
+    pub fn set_sampler_settings(&mut self, sampler_settings: SamplerSettings) -> &mut Self {
+        self.sampler_settings = sampler_settings;
+        self.needs_prepare = true;
+        self
+    }
+
+    pub fn get_sampler_settings(&self) -> SamplerSettings {
+        self.sampler_settings
+    }
+
+    pub fn is_prepared(&self) -> bool {
+        !self.needs_prepare
+    }
+
+    pub(crate) fn get_descriptor_set(&self) -> Option<vk::DescriptorSet> {
+        self.descriptor_set
+    }
+
+    /// Creates the sampler and the descriptor set binding the atlas of the material.
+    ///
+    /// `atlas` is the atlas of the texture, or the fallback atlas if the material has none.
+    pub unsafe fn prepare(
+        &mut self,
+        device: &Device,
+        layout: vk::DescriptorSetLayout,
+        atlas: &Atlas,
+    ) -> Result<()> {
+        self.cleanup(device);
+
+        // Sampler
+
+        let settings = self.sampler_settings;
+        let (mipmap_mode, max_lod) = match settings.mipmaps {
+            true => (
+                vk::SamplerMipmapMode::LINEAR,
+                (atlas.get_mip_levels() - 1) as f32,
+            ),
+            false => (vk::SamplerMipmapMode::NEAREST, 0.0),
+        };
+
+        let info = vk::SamplerCreateInfo::builder()
+            .mag_filter(settings.mag_filter)
+            .min_filter(settings.min_filter)
+            .address_mode_u(settings.address_mode)
+            .address_mode_v(settings.address_mode)
+            .address_mode_w(settings.address_mode)
+            .anisotropy_enable(settings.anisotropy.is_some())
+            .max_anisotropy(settings.anisotropy.unwrap_or(1.0))
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .mipmap_mode(mipmap_mode)
+            .min_lod(0.0)
+            .max_lod(max_lod)
+            .mip_lod_bias(0.0);
+
+        let sampler = device.create_sampler(&info, None)?;
+        self.sampler = Some(sampler);
+
+        // Pool
+
+        let sampler_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1);
+
+        let pool_sizes = &[sampler_size];
+        let info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(1);
+
+        let descriptor_pool = device.create_descriptor_pool(&info, None)?;
+        self.descriptor_pool = Some(descriptor_pool);
+
+        // Allocate
+
+        let layouts = &[layout];
+        let info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(layouts);
+
+        let descriptor_set = device.allocate_descriptor_sets(&info)?[0];
+
+        // Update
+
+        let info = vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(atlas.get_image_view().unwrap_or_default())
+            .sampler(sampler);
+
+        let image_info = &[info];
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(image_info);
+
+        device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
+
+        self.descriptor_set = Some(descriptor_set);
+        self.needs_prepare = false;
+
+        Ok(())
+    }
+
+    pub fn cleanup(&mut self, device: &Device) {
+        unsafe {
+            if let Some(descriptor_pool) = self.descriptor_pool.take() {
+                device.destroy_descriptor_pool(descriptor_pool, None);
+            }
+            if let Some(sampler) = self.sampler.take() {
+                device.destroy_sampler(sampler, None);
+            }
+        }
+        self.descriptor_set = None;
+        self.needs_prepare = true;
+    }
 }
@@ -1,14 +1,35 @@
+use std::collections::HashMap;
+
+use cgmath::{EuclideanSpace, InnerSpace, Zero};
+
+use super::handle::Handle;
+use super::math::{Point3, Vec3, Vec4};
 use super::meshbufferdata::MeshBufferData;
 use super::object::Object;
+use super::texture::Texture;
 use super::vertex::Vertex;
+use super::vertexlayout::VertexLayout;
 use std::mem::size_of;
 
+/// How `Mesh::compute_normals` shades across the edges between triangles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalMode {
+    /// Averages the normals of all triangles meeting at a position.
+    Smooth,
+    /// Gives every triangle its own face normal.
+    Flat,
+    /// Averages across edges where the triangles meet at less than the given angle in degrees
+    /// and keeps the other edges hard.
+    AngleThreshold(f32),
+}
+
 #[derive(Default)]
 pub struct Mesh {
-    handle: usize,
+    handle: Handle<Mesh>,
     name: Option<String>,
     pub(crate) vertices: Vec<Vertex>,
     pub(crate) indices: Vec<u32>,
+    layout: VertexLayout,
     pub(crate) mesh_buffer_data: Option<MeshBufferData>,
 }
 
@@ -17,11 +38,19 @@ impl Object for Mesh {
         self.name.clone()
     }
 
-    fn set_handle(&mut self, handle: usize) {
+    fn has_name(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name)
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn set_handle(&mut self, handle: Handle<Self>) {
         self.handle = handle;
     }
 
-    fn get_handle(&self) -> usize {
+    fn get_handle(&self) -> Handle<Self> {
         self.handle
     }
 }  
@@ -29,16 +58,172 @@ impl Object for Mesh {
 impl Mesh {
     pub fn new(name: Option<String>, vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
         Self {
-            handle: usize::MAX,
-            name: None,
+            handle: Handle::invalid(),
+            name,
             vertices,
             indices,
+            layout: VertexLayout::default(),
             mesh_buffer_data: None,
         }
     }
 
+    pub fn get_layout(&self) -> &VertexLayout {
+        &self.layout
+    }
+
+    /// Sets the attributes uploaded for each vertex. Must be set before the mesh is added to a
+    /// scene, and contain the inputs of every shader the mesh is drawn with; the mesh is not
+    /// drawn with shaders reading attributes it lacks.
+    pub fn set_layout(&mut self, layout: VertexLayout) {
+        self.layout = layout;
+    }
+
+    /// Maps the texture coordinates of all vertices into the window of the texture.
+    pub fn remap_uvs(&mut self, texture: &Texture) {
+        for vertex in &mut self.vertices {
+            vertex.tex_coord = texture.map_uv(vertex.tex_coord);
+        }
+    }
+
+    /// Replaces the normals with ones computed from the triangles, weighting each triangle by
+    /// its angle at the vertex. Vertices are split where hard edges need different normals, so
+    /// tangents have to be computed afterwards.
+    pub fn compute_normals(&mut self, mode: NormalMode) {
+        let cos_threshold = match mode {
+            NormalMode::Smooth => -2.0,
+            NormalMode::Flat => 2.0,
+            NormalMode::AngleThreshold(degrees) => degrees.to_radians().cos(),
+        };
+
+        let triangles = self.indices.chunks_exact(3).collect::<Vec<_>>();
+        let faces = triangles
+            .iter()
+            .map(|t| {
+                let corners = [0, 1, 2].map(|i| self.vertices[t[i] as usize].pos);
+                let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+                let normal = if normal.magnitude2() > 0.0 {
+                    normal.normalize()
+                } else {
+                    normal
+                };
+                (normal, corner_angles(corners))
+            })
+            .collect::<Vec<_>>();
+
+        // Vertices split along texture seams still share their position.
+        let mut corners_at = HashMap::<[u32; 3], Vec<(usize, usize)>>::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            for (k, index) in triangle.iter().enumerate() {
+                let key = position_key(self.vertices[*index as usize].pos);
+                corners_at.entry(key).or_default().push((t, k));
+            }
+        }
+
+        // Each vertex keeps the normal of its first corner; corners needing another normal get
+        // a copy of it.
+        let mut assigned = vec![None; self.vertices.len()];
+        let mut copies = HashMap::new();
+        let corners = self.indices[..faces.len() * 3].to_vec();
+        for (corner, index) in corners.into_iter().enumerate() {
+            let t = corner / 3;
+            let vertex = self.vertices[index as usize];
+            let face = faces[t].0;
+            let sum = corners_at[&position_key(vertex.pos)]
+                .iter()
+                .filter(|(other, _)| *other == t || face.dot(faces[*other].0) >= cos_threshold)
+                .fold(Vec3::zero(), |sum, (other, k)| {
+                    sum + faces[*other].0 * faces[*other].1[*k]
+                });
+            let normal = if sum.magnitude2() > 0.0 {
+                sum.normalize()
+            } else {
+                face
+            };
+
+            let bits = [normal.x, normal.y, normal.z].map(f32::to_bits);
+            match assigned[index as usize] {
+                None => {
+                    assigned[index as usize] = Some(bits);
+                    self.vertices[index as usize].normal = normal;
+                }
+                Some(first) if first == bits => {}
+                Some(_) => {
+                    let vertices = &mut self.vertices;
+                    self.indices[corner] = *copies.entry((index, bits)).or_insert_with(|| {
+                        vertices.push(Vertex { normal, ..vertex });
+                        vertices.len() as u32 - 1
+                    });
+                }
+            }
+        }
+    }
+
+    /// Computes the tangents from the texture coordinates with MikkTSpace, the tangent space
+    /// normal maps are commonly baked in. Vertices are split where their triangles need
+    /// different tangents, e.g. on mirrored texture seams; vertices without usable texture
+    /// coordinates get an arbitrary tangent.
+    pub fn compute_tangents(&mut self) {
+        for vertex in &mut self.vertices {
+            vertex.tangent = any_tangent(vertex.normal).extend(1.0);
+        }
+
+        let mut tangent_space = TangentSpace {
+            mesh: self,
+            tangents: vec![None; self.indices.len() / 3 * 3],
+        };
+        bevy_mikktspace::generate_tangents(&mut tangent_space);
+        let tangents = tangent_space.tangents;
+
+        // Each vertex keeps the tangent of its first corner; corners needing another tangent get
+        // a copy of it.
+        let mut assigned = vec![None; self.vertices.len()];
+        let mut copies = HashMap::new();
+        for (corner, tangent) in tangents.into_iter().enumerate() {
+            let index = self.indices[corner];
+            let vertex = self.vertices[index as usize];
+            // Without texture coordinates MikkTSpace reports tangents off the surface.
+            let tangent = tangent
+                .map(|t| {
+                    (
+                        t.truncate() - vertex.normal * vertex.normal.dot(t.truncate()),
+                        t.w,
+                    )
+                })
+                .filter(|(t, _)| t.magnitude2() > 1e-12)
+                .map_or(vertex.tangent, |(t, w)| t.normalize().extend(w));
+            let bits = [tangent.x, tangent.y, tangent.z, tangent.w].map(f32::to_bits);
+            match assigned[index as usize] {
+                None => {
+                    assigned[index as usize] = Some(bits);
+                    self.vertices[index as usize].tangent = tangent;
+                }
+                Some(first) if first == bits => {}
+                Some(_) => {
+                    let vertices = &mut self.vertices;
+                    self.indices[corner] = *copies.entry((index, bits)).or_insert_with(|| {
+                        vertices.push(Vertex { tangent, ..vertex });
+                        vertices.len() as u32 - 1
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns the corners of the local space box around all vertices, or `None` if the mesh
+    /// is empty.
+    pub fn get_bounds(&self) -> Option<(Point3, Point3)> {
+        let first = self.vertices.first()?.pos;
+        let (min, max) = self.vertices.iter().fold((first, first), |(min, max), v| {
+            (
+                Vec3::new(min.x.min(v.pos.x), min.y.min(v.pos.y), min.z.min(v.pos.z)),
+                Vec3::new(max.x.max(v.pos.x), max.y.max(v.pos.y), max.z.max(v.pos.z)),
+            )
+        });
+        Some((Point3::from_vec(min), Point3::from_vec(max)))
+    }
+
     pub fn data_size_for_vertexes(&self) -> usize {
-        size_of::<Vertex>() * self.vertices.len()
+        self.layout.get_stride() * self.vertices.len()
     }
 
     pub fn data_size_for_indexes(&self) -> usize {
@@ -53,7 +238,7 @@ impl Mesh {
         self.indices.len()
     }
 
-    pub fn set_mesh_buffer_data(&mut self, buffer_data: MeshBufferData) {
+    pub(crate) fn set_mesh_buffer_data(&mut self, buffer_data: MeshBufferData) {
         self.mesh_buffer_data = Some(buffer_data);
     }
 
@@ -61,3 +246,254 @@ impl Mesh {
         self.mesh_buffer_data.is_some()
     }
 }
+
+/// The triangles of a mesh as seen by MikkTSpace, which reports a tangent for every corner.
+struct TangentSpace<'a> {
+    mesh: &'a Mesh,
+    tangents: Vec<Option<Vec4>>,
+}
+
+impl TangentSpace<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.mesh.vertices[self.mesh.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentSpace<'_> {
+    fn num_faces(&self) -> usize {
+        self.mesh.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).pos.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).tex_coord.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = Some(tangent.into());
+    }
+}
+
+/// Returns some unit vector orthogonal to the normal.
+fn any_tangent(normal: Vec3) -> Vec3 {
+    let other = if normal.x.abs() < 0.9 {
+        Vec3::unit_x()
+    } else {
+        Vec3::unit_y()
+    };
+    let tangent = normal.cross(other).cross(normal);
+    if tangent.magnitude2() > 0.0 {
+        tangent.normalize()
+    } else {
+        Vec3::unit_x()
+    }
+}
+
+/// Returns a key equal for equal positions, treating -0 and 0 alike.
+fn position_key(pos: Vec3) -> [u32; 3] {
+    [pos.x, pos.y, pos.z].map(|c| (c + 0.0).to_bits())
+}
+
+/// Returns the interior angles of a triangle at its three corners, 0 for degenerate corners.
+fn corner_angles(corners: [Vec3; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| {
+        let to_next = corners[(i + 1) % 3] - corners[i];
+        let to_previous = corners[(i + 2) % 3] - corners[i];
+        if to_next.magnitude2() == 0.0 || to_previous.magnitude2() == 0.0 {
+            return 0.0;
+        }
+        to_next
+            .normalize()
+            .dot(to_previous.normalize())
+            .clamp(-1.0, 1.0)
+            .acos()
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::feather::math::{Vec2, Vec4};
+    use crate::feather::meshbuilder::MeshBuilder;
+    use crate::feather::meshbuilderplane::MeshBuilderPlane;
+    use crate::feather::meshbuilderuvsphere::MeshBuilderUvSphere;
+
+    fn face_normal(mesh: &Mesh, triangle: &[u32]) -> Vec3 {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].pos);
+        (b - a).cross(c - a)
+    }
+
+    /// Checks that every normal has unit length and that every triangle winds counter-clockwise
+    /// around the normals of its vertices.
+    pub(crate) fn assert_normals(mesh: &Mesh) {
+        for vertex in &mesh.vertices {
+            let length = vertex.normal.magnitude();
+            assert!((length - 1.0).abs() < 1e-4, "{:?}", vertex);
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let face = face_normal(mesh, triangle);
+            for index in triangle {
+                let normal = mesh.vertices[*index as usize].normal;
+                assert!(
+                    face.dot(normal) > 0.0,
+                    "{:?} winds against {:?}",
+                    triangle,
+                    normal
+                );
+            }
+        }
+    }
+
+    /// Checks that the triangles form a closed, consistently wound surface: welding vertices at
+    /// equal positions, every edge is used once in each direction.
+    pub(crate) fn assert_closed(mesh: &Mesh) {
+        let mut welded = HashMap::new();
+        let ids = mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                let key = [v.pos.x, v.pos.y, v.pos.z].map(|c| (c * 1e4).round() as i64);
+                let next = welded.len();
+                *welded.entry(key).or_insert(next)
+            })
+            .collect::<Vec<_>>();
+
+        let mut edges = HashMap::new();
+        for triangle in mesh.indices.chunks_exact(3) {
+            assert!(
+                face_normal(mesh, triangle).magnitude() > 0.0,
+                "{:?}",
+                triangle
+            );
+            for i in 0..3 {
+                let edge = (
+                    ids[triangle[i] as usize],
+                    ids[triangle[(i + 1) % 3] as usize],
+                );
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1, "edge {:?} is used {} times", (a, b), count);
+            assert!(edges.contains_key(&(b, a)), "edge {:?} is open", (a, b));
+        }
+    }
+
+    /// Two unit squares folded by 90 degrees along the Y axis, sharing the vertices of the
+    /// fold: one in the XY plane facing +Z and one in the YZ plane facing +X.
+    fn folded() -> Mesh {
+        let vertex = |x: f32, y: f32, z: f32| {
+            Vertex::new(Vec3::new(x, y, z), Vec3::zero(), Vec2::new(x - z, y))
+        };
+        Mesh::new(
+            None,
+            vec![
+                vertex(0.0, 0.0, 0.0),
+                vertex(0.0, 1.0, 0.0),
+                vertex(-1.0, 0.0, 0.0),
+                vertex(-1.0, 1.0, 0.0),
+                vertex(0.0, 0.0, -1.0),
+                vertex(0.0, 1.0, -1.0),
+            ],
+            vec![2, 0, 1, 2, 1, 3, 0, 4, 5, 0, 5, 1],
+        )
+    }
+
+    #[test]
+    fn test_compute_normals() {
+        let mut mesh = folded();
+        mesh.compute_normals(NormalMode::Smooth);
+        assert_eq!(mesh.vertices.len(), 6);
+        let diagonal = Vec3::new(1.0, 0.0, 1.0).normalize();
+        assert!((mesh.vertices[0].normal - diagonal).magnitude() < 1e-6);
+        assert_normals(&mesh);
+
+        // The fold is sharper than 60 degrees, so its vertices are split.
+        for mode in [NormalMode::Flat, NormalMode::AngleThreshold(60.0)] {
+            let mut mesh = folded();
+            mesh.compute_normals(mode);
+            assert_eq!(mesh.vertices.len(), 8);
+            assert_eq!(mesh.vertices[0].normal, Vec3::unit_z());
+            assert_normals(&mesh);
+        }
+        let mut mesh = folded();
+        mesh.compute_normals(NormalMode::AngleThreshold(120.0));
+        assert_eq!(mesh.vertices.len(), 6);
+
+        // Vertices split along a texture seam are still smoothed together.
+        let mut sphere = MeshBuilderUvSphere::new(2.0).build_mesh().unwrap();
+        sphere.vertices.iter_mut().for_each(|v| v.normal = Vec3::zero());
+        sphere.compute_normals(NormalMode::Smooth);
+        for vertex in &sphere.vertices {
+            assert!((vertex.normal - vertex.pos / 2.0).magnitude() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_compute_tangents() {
+        let mut mesh = folded();
+        mesh.compute_normals(NormalMode::Flat);
+        mesh.compute_tangents();
+        // u grows along -Z on the second square, v along +Y on both.
+        assert_eq!(mesh.vertices[0].tangent, Vec4::new(1.0, 0.0, 0.0, 1.0));
+        let side = mesh.vertices.iter().find(|v| v.normal == Vec3::unit_x()).unwrap();
+        assert!((side.tangent - Vec4::new(0.0, 0.0, -1.0, 1.0)).magnitude() < 1e-6);
+
+        // The plane maps v downwards along -Y, which flips the bitangent.
+        let mut plane = MeshBuilderPlane::new(2.0, 2.0).build_mesh().unwrap();
+        plane.compute_tangents();
+        for vertex in &plane.vertices {
+            assert_eq!(vertex.tangent, Vec4::new(1.0, 0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn test_mirrored_tangents() {
+        // Two squares side by side in the XY plane whose texture is mirrored at x = 1.
+        let vertex = |x: f32, y: f32| {
+            Vertex::new(
+                Vec3::new(x, y, 0.0),
+                Vec3::unit_z(),
+                Vec2::new(1.0 - (x - 1.0).abs(), y),
+            )
+        };
+        let mut mesh = Mesh::new(
+            None,
+            vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(2.0, 0.0),
+                vertex(0.0, 1.0),
+                vertex(1.0, 1.0),
+                vertex(2.0, 1.0),
+            ],
+            vec![0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4],
+        );
+        mesh.compute_tangents();
+
+        // The vertices of the seam are split, as the halves disagree on the tangent and its sign.
+        assert_eq!(mesh.vertices.len(), 8);
+        for (triangle, expected) in mesh.indices.chunks_exact(3).zip([
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(-1.0, 0.0, 0.0, -1.0),
+            Vec4::new(-1.0, 0.0, 0.0, -1.0),
+        ]) {
+            for index in triangle {
+                let tangent = mesh.vertices[*index as usize].tangent;
+                assert!((tangent - expected).magnitude() < 1e-6, "{:?}", tangent);
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::mem::size_of;
 use std::ptr::copy_nonoverlapping;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use vulkanalia::prelude::v1_0::*;
 use vulkanalia::vk;
@@ -14,19 +14,21 @@ use vulkanalia::Instance;
 use crate::feather::buffers::copy_buffer;
 use crate::feather::buffers::create_buffer;
 use crate::feather::meshbufferdata::MeshBufferData;
-use crate::feather::scene::Scene;
-use crate::feather::vertex::Vertex;
 
+use super::handle::Handle;
 use super::mesh::Mesh;
 use super::objdb::ObjDB;
 use super::object::Object;
+use super::vertexlayout::VertexLayout;
 
 #[derive(Default)]
 pub struct MeshBuffer {
-    handle: usize,
+    handle: Handle<MeshBuffer>,
+    layout: VertexLayout,
     num_vertexes: usize,
     num_indexes: usize,
-    mesh_handles: Vec<usize>,
+    /// The meshes in the buffer with their number of vertices and indices, in buffer order.
+    mesh_handles: Vec<(Handle<Mesh>, usize, usize)>,
     pub vertex_buffer: Option<vk::Buffer>,
     pub vertex_buffer_memory: Option<vk::DeviceMemory>,
     pub index_buffer: Option<vk::Buffer>,
@@ -38,19 +40,23 @@ impl Object for MeshBuffer {
         None
     }
 
-    fn set_handle(&mut self, handle: usize) {
+    fn set_name(&mut self, _name: Option<String>) {}
+
+    fn set_handle(&mut self, handle: Handle<Self>) {
         self.handle = handle;
     }
 
-    fn get_handle(&self) -> usize {
+    fn get_handle(&self) -> Handle<Self> {
         self.handle
     }
 }
 
 impl MeshBuffer {
-    pub fn new() -> Self {
+    /// Creates an empty buffer for meshes with the given vertex layout.
+    pub fn new(layout: VertexLayout) -> Self {
         Self {
-            handle: 0,
+            handle: Handle::invalid(),
+            layout,
             num_vertexes: 0,
             num_indexes: 0,
             mesh_handles: Vec::new(),
@@ -61,22 +67,38 @@ impl MeshBuffer {
         }
     }
 
-    pub fn add_mesh(&mut self, mesh: &mut Mesh) {
-        self.mesh_handles.push(mesh.get_handle());
+    pub fn get_layout(&self) -> &VertexLayout {
+        &self.layout
+    }
+
+    pub fn add_mesh(&mut self, mesh: &mut Mesh) -> Result<()> {
+        if *mesh.get_layout() != self.layout {
+            return Err(anyhow!("Mesh vertex layout differs from the mesh buffer"));
+        }
+        if mesh.vertices.is_empty() || mesh.indices.is_empty() {
+            return Err(anyhow!(
+                "Meshes without vertices or indices cannot be buffered"
+            ));
+        }
+        self.mesh_handles.push((
+            mesh.get_handle(),
+            mesh.gen_num_vertexes(),
+            mesh.gen_num_indexes(),
+        ));
         let mesh_buffer_data = MeshBufferData::new(
             self.handle,
             self.num_vertexes,
-            mesh.gen_num_vertexes(),
             self.num_indexes,
             mesh.gen_num_indexes(),
         );
         mesh.set_mesh_buffer_data(mesh_buffer_data);
         self.num_vertexes += mesh.gen_num_vertexes();
         self.num_indexes += mesh.gen_num_indexes();
+        Ok(())
     }
 
     pub fn data_size_for_vertexes(&self) -> usize {
-        self.num_vertexes * size_of::<Vertex>()
+        self.num_vertexes * self.layout.get_stride()
     }
 
     pub fn data_size_for_indexes(&self) -> usize {
@@ -110,15 +132,20 @@ impl MeshBuffer {
         let memory =
             device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
 
+        // Meshes removed from the scene since they were added keep their space, so the
+        // offsets of the others stay valid.
         let mut offset = 0usize;
-        for mesh_handle in &self.mesh_handles {
-            let mesh = meshes.get(*mesh_handle).unwrap();
-            copy_nonoverlapping(
-                mesh.vertices.as_ptr(),
-                memory.cast::<crate::feather::vertex::Vertex>().add(offset),
-                mesh.gen_num_vertexes(),
-            );
-            offset += mesh.gen_num_vertexes();
+        for (mesh_handle, num_vertexes, _) in &self.mesh_handles {
+            let size = num_vertexes * self.layout.get_stride();
+            if let Some(mesh) = meshes.get(*mesh_handle) {
+                let bytes = self.layout.pack(&mesh.vertices);
+                copy_nonoverlapping(
+                    bytes.as_ptr(),
+                    memory.cast::<u8>().add(offset),
+                    bytes.len().min(size),
+                );
+            }
+            offset += size;
         }
 
         device.unmap_memory(staging_buffer_memory);
@@ -184,14 +211,15 @@ impl MeshBuffer {
             device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
 
         let mut offset = 0usize;
-        for mesh_handle in &self.mesh_handles {
-            let mesh = meshes.get(*mesh_handle).unwrap();
-            copy_nonoverlapping(
-                mesh.indices.as_ptr(),
-                memory.cast::<u32>().add(offset),
-                mesh.gen_num_indexes(),
-            );
-            offset += mesh.gen_num_indexes();
+        for (mesh_handle, _, num_indexes) in &self.mesh_handles {
+            if let Some(mesh) = meshes.get(*mesh_handle) {
+                copy_nonoverlapping(
+                    mesh.indices.as_ptr(),
+                    memory.cast::<u32>().add(offset),
+                    mesh.indices.len().min(*num_indexes),
+                );
+            }
+            offset += num_indexes;
         }
 
         device.unmap_memory(staging_buffer_memory);
@@ -257,6 +285,10 @@ impl MeshBuffer {
         graphics_queue: &Queue,
         meshes: &ObjDB<Mesh>,
     ) -> Result<()> {
+        // Vulkan does not allow empty buffers.
+        if self.num_vertexes == 0 || self.num_indexes == 0 {
+            return Ok(());
+        }
         unsafe {
             self.cleanup(device);
             self.create_vertex_buffer(
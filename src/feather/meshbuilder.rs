@@ -0,0 +1,18 @@
+use anyhow::Result;
+
+use super::handle::Handle;
+use super::mesh::Mesh;
+use super::scene::Scene;
+
+/// Creates the geometry of a `Mesh` on the CPU.
+///
+/// Builders can be kept as `Box<dyn MeshBuilder>`, built more than once and combined with
+/// `MeshBuilderGroup`.
+pub trait MeshBuilder {
+    fn build_mesh(&self) -> Result<Mesh>;
+
+    /// Builds the mesh and adds it to the scene.
+    fn build_into(&self, scene: &mut Scene) -> Result<Handle<Mesh>> {
+        scene.add_mesh(self.build_mesh()?)
+    }
+}
@@ -0,0 +1,123 @@
+use std::f32::consts::PI;
+
+use anyhow::Result;
+
+use super::math::Vec2;
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::{MeshBuilderLathe, ProfilePoint};
+use super::texture::Texture;
+
+/// Builds a capsule around the Z axis, centered on the origin: a cylinder closed by two
+/// hemispheres. The texture is wrapped around once and stretched from the top pole (v = 0) to
+/// the bottom pole by arc length.
+pub struct MeshBuilderCapsule {
+    radius: f32,
+    height: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderCapsule {
+    /// Creates a capsule of the given total height, which is at least twice the radius.
+    pub fn new(radius: f32, height: f32) -> Self {
+        Self {
+            radius,
+            height: height.max(2.0 * radius),
+            segments: 32,
+            rings: 8,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the Z axis, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of rings of each hemisphere, at least 1.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(1);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderCapsule {
+    fn build_mesh(&self) -> Result<Mesh> {
+        // Half the length of the cylinder between the centers of the hemispheres.
+        let half = self.height / 2.0 - self.radius;
+        let quarter = PI / 2.0 * self.radius;
+        let length = 2.0 * quarter + 2.0 * half;
+
+        let mut profile = Vec::new();
+        let mut add_hemisphere = |center: f32, start: f32, first_ring: u32, top: bool| {
+            for ring in first_ring..=self.rings {
+                let t = ring as f32 / self.rings as f32;
+                let angle = if top { t } else { t - 1.0 } * PI / 2.0;
+                let pole = (top && ring == self.rings) || (!top && ring == 0);
+                let (sin, cos) = if pole {
+                    (angle.signum(), 0.0)
+                } else {
+                    angle.sin_cos()
+                };
+                let distance = start + t * quarter;
+                profile.push(ProfilePoint::new(
+                    self.radius * cos,
+                    center + self.radius * sin,
+                    Vec2::new(cos, sin),
+                    1.0 - distance / length,
+                ));
+            }
+        };
+        add_hemisphere(-half, 0.0, 0, false);
+        // Without a cylinder between them the hemispheres share their equator.
+        let first_ring = if half > 0.0 { 0 } else { 1 };
+        add_hemisphere(half, quarter + 2.0 * half, first_ring, true);
+
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe.add_strip(&profile).build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_capsule() {
+        let mesh = MeshBuilderCapsule::new(0.5, 2.0)
+            .set_segments(8)
+            .set_rings(3)
+            .build_mesh()
+            .unwrap();
+
+        // Three rings of 8 + 1 seam vertices per hemisphere and one vertex per segment at each
+        // pole.
+        assert_eq!(mesh.vertices.len(), 6 * 9 + 2 * 8);
+        assert_eq!(mesh.indices.len(), 12 * 3 * 8);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+
+        // A capsule as short as it is wide is a sphere.
+        let mesh = MeshBuilderCapsule::new(0.5, 0.0)
+            .set_segments(8)
+            .set_rings(3)
+            .build_mesh()
+            .unwrap();
+        assert_eq!(mesh.vertices.len(), 5 * 9 + 2 * 8);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+    }
+}
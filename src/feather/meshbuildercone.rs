@@ -0,0 +1,96 @@
+use anyhow::Result;
+use cgmath::InnerSpace;
+
+use super::math::Vec2;
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::{MeshBuilderLathe, ProfilePoint};
+use super::texture::Texture;
+
+/// Builds a closed cone around the Z axis, centered on the origin with the tip pointing up. The
+/// side wraps the texture once around, the base maps it flat.
+pub struct MeshBuilderCone {
+    radius: f32,
+    height: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderCone {
+    pub fn new(radius: f32, height: f32) -> Self {
+        Self {
+            radius,
+            height,
+            segments: 32,
+            rings: 1,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the Z axis, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of rings the side is divided into from the base to the tip, at least 1.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(1);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderCone {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let half = self.height / 2.0;
+        let normal = Vec2::new(self.height, self.radius).normalize();
+        let profile = (0..=self.rings)
+            .map(|ring| {
+                let t = ring as f32 / self.rings as f32;
+                ProfilePoint::new(
+                    self.radius * (1.0 - t),
+                    -half + t * self.height,
+                    normal,
+                    1.0 - t,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe
+            .add_strip(&profile)
+            .add_cap(self.radius, -half, false, 1)
+            .build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_cone() {
+        let mesh = MeshBuilderCone::new(1.0, 2.0)
+            .set_segments(5)
+            .set_rings(2)
+            .build_mesh()
+            .unwrap();
+
+        // Two rings of 5 + 1 seam vertices, one tip vertex per segment and the base.
+        assert_eq!(mesh.vertices.len(), 2 * 6 + 5 + 6);
+        assert_eq!(mesh.indices.len(), 5 * 6 + 5 * 3 + 5 * 3);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+    }
+}
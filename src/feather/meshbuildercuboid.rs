@@ -1,8 +1,10 @@
-use anyhow::{bail, Result};
+use anyhow::Result;
+use cgmath::InnerSpace;
 
 use super::math::{Vec2, Vec3};
 use super::mesh::Mesh;
-use super::scene::Scene;
+use super::meshbuilder::MeshBuilder;
+use super::texture::Texture;
 use super::vertex::Vertex;
 
 //   ^ y
@@ -23,17 +25,51 @@ use super::vertex::Vertex;
 //   |/                5
 //   -----------------------------------------------------------> x
 
+/// A wall of a `MeshBuilderCuboid`, numbered as in the diagram above.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CuboidFace {
+    /// Wall 0 at `z.0`, facing -Z.
+    MinZ = 0,
+    /// Wall 1 at `x.1`, facing +X.
+    MaxX = 1,
+    /// Wall 2 at `z.1`, facing +Z.
+    MaxZ = 2,
+    /// Wall 3 at `x.0`, facing -X.
+    MinX = 3,
+    /// Wall 4 at `y.1`, facing +Y.
+    MaxY = 4,
+    /// Wall 5 at `y.0`, facing -Y.
+    MinY = 5,
+}
+
+impl CuboidFace {
+    pub const ALL: [CuboidFace; 6] = [
+        CuboidFace::MinZ,
+        CuboidFace::MaxX,
+        CuboidFace::MaxZ,
+        CuboidFace::MinX,
+        CuboidFace::MaxY,
+        CuboidFace::MinY,
+    ];
+}
+
+/// Builds an axis aligned box from the six walls of `CuboidFace`.
+///
+/// Every wall faces outwards, winds counter-clockwise seen from outside and shows its texture
+/// upright: the sides with v growing downwards along -Y, the top with v growing towards +Z and
+/// the bottom with v growing towards -Z.
 pub struct MeshBuilderCuboid {
     x: (f32, f32),
     y: (f32, f32),
     z: (f32, f32),
-    u: Vec<(f32, f32)>,
-    v: Vec<(f32, f32)>,
-    vertices: Vec<Vertex>,
-    indices: Vec<u32>,
+    u: [(f32, f32); 6],
+    v: [(f32, f32); 6],
+    subdivisions: [(u32, u32); 6],
 }
 
 impl MeshBuilderCuboid {
+    /// Creates a box spanning the given ranges, which must be ascending. Wall `i` maps the
+    /// texture range `u[i % u.len()]` by `v[i % v.len()]`; by default the whole texture.
     pub fn new(
         x: (f32, f32),
         y: (f32, f32),
@@ -41,32 +77,17 @@ impl MeshBuilderCuboid {
         u: Option<Vec<(f32, f32)>>,
         v: Option<Vec<(f32, f32)>>,
     ) -> Self {
+        let per_wall = |ranges: Option<Vec<(f32, f32)>>| {
+            let ranges = ranges.filter(|r| !r.is_empty()).unwrap_or(vec![(0.0, 1.0)]);
+            [0, 1, 2, 3, 4, 5].map(|i| ranges[i % ranges.len()])
+        };
         Self {
             x,
             y,
             z,
-            u: match u {
-                Some(u) => {
-                    if u.len() > 0 {
-                        u
-                    } else {
-                        vec![(0.0, 1.0)]
-                    }
-                }
-                None => vec![(0.0, 1.0)],
-            },
-            v: match v {
-                Some(v) => {
-                    if v.len() > 0 {
-                        v
-                    } else {
-                        vec![(0.0, 1.0)]
-                    }
-                }
-                None => vec![(0.0, 1.0)],
-            },
-            vertices: Vec::new(),
-            indices: Vec::new(),
+            u: per_wall(u),
+            v: per_wall(v),
+            subdivisions: [(1, 1); 6],
         }
     }
 
@@ -74,106 +95,172 @@ impl MeshBuilderCuboid {
         Self::new(x, y, z, None, None)
     }
 
-    pub fn build(mut self, scene: &mut Scene) -> Result<usize> {
-
-        // 0
-        self.add_wall(
-            (self.x.0, self.x.1),
-            (self.y.0, self.y.1),
-            (self.z.0, self.z.0),
-            self.u[0 % self.u.len()],
-            self.v[0 % self.v.len()],
-            (0.0, 0.0, 1.0),
-        )?;
-
-        // 1
-        self.add_wall(
-            (self.x.1, self.x.1),
-            (self.y.0, self.y.1),
-            (self.z.1, self.z.0),
-            self.u[1 % self.u.len()],
-            self.v[1 % self.v.len()],
-            (1.0, 0.0, 0.0),
-        )?;
-
-        // 2
-        self.add_wall(
-            (self.x.1, self.x.0),
-            (self.y.0, self.y.1),
-            (self.z.1, self.z.1),
-            self.u[2 % self.u.len()],
-            self.v[2 % self.v.len()],
-            (0.0, 0.0, -1.0),
-        )?;
-
-        // 3
-        self.add_wall(
-            (self.x.0, self.x.0),
-            (self.y.1, self.y.0),
-            (self.z.1, self.z.0),
-            self.u[3 % self.u.len()],
-            self.v[3 % self.v.len()],
-            (-1.0, 0.0, 0.0),
-        )?;
-
-        // 4
-        self.add_wall(
-            (self.x.0, self.x.1),
-            (self.y.0, self.y.0),
-            (self.z.1, self.z.0),
-            self.u[4 % self.u.len()],
-            self.v[4 % self.v.len()],
-            (0.0, 1.0, 0.0),
-        )?;
-
-        // 5
-        self.add_wall(
-            (self.x.1, self.x.0),
-            (self.y.1, self.y.1),
-            (self.z.1, self.z.0),
-            self.u[5 % self.u.len()],
-            self.v[5 % self.v.len()],
-            (0.0, -1.0, 0.0),
-        )?;
-
-        let mesh = Mesh::new(None, self.vertices, self.indices);
-        Ok(scene.add_mesh(mesh))
+    /// Maps the window of an atlas texture onto every wall.
+    pub fn new_with_texture(
+        x: (f32, f32),
+        y: (f32, f32),
+        z: (f32, f32),
+        texture: &Texture,
+    ) -> Self {
+        Self::new(
+            x,
+            y,
+            z,
+            Some(vec![texture.get_u()]),
+            Some(vec![texture.get_v()]),
+        )
+    }
+
+    /// Maps the window of an atlas texture onto one wall.
+    pub fn set_face_texture(mut self, face: CuboidFace, texture: &Texture) -> Self {
+        self.u[face as usize] = texture.get_u();
+        self.v[face as usize] = texture.get_v();
+        self
+    }
+
+    /// Divides every wall into a grid of `columns` by `rows` quads, along its u and v.
+    ///
+    /// Every wall has vertices of its own. Unless both numbers are the same, neighbouring walls
+    /// divide their common edge differently, and the resulting T-junctions can show cracks.
+    pub fn set_subdivisions(mut self, columns: u32, rows: u32) -> Self {
+        self.subdivisions = [(columns.max(1), rows.max(1)); 6];
+        self
+    }
+
+    /// Divides one wall into a grid of `columns` by `rows` quads. Where its edges do not match
+    /// the division of the neighbouring walls, the T-junctions can show cracks.
+    pub fn set_face_subdivisions(mut self, face: CuboidFace, columns: u32, rows: u32) -> Self {
+        self.subdivisions[face as usize] = (columns.max(1), rows.max(1));
+        self
     }
 
-    #[rustfmt::skip]
-    fn add_wall(&mut self, x:(f32,f32), y:(f32,f32), z:(f32,f32), u:(f32,f32), v:(f32,f32), n:(f32, f32, f32) ) -> Result<()> {
-        let start_pos = self.vertices.len() as u32;
-
-        if z.0 == z.1 {
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.0, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.1, y:y.0, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.1, y:y.1, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.1}));
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.1, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.1}));
-		}
-		else if y.0 == y.1 {
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.0, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.1, y:y.0, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.1, y:y.0, z:z.1}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.1}));
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.0, z:z.1}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.1}));
-		}
-		else if x.0 == x.1 {
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.0, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.1, z:z.0}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.0}));
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.1, z:z.1}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.1, y:v.1}));
-			self.vertices.push(Vertex::new(Vec3{x:x.0, y:y.0, z:z.1}, Vec3{x:n.0, y:n.1, z:n.2}, Vec2{x:u.0, y:v.1}));
-		}
-		else {
-			bail!("Invalid cuboid wall");
+    /// Returns the corner of a wall where its texture starts, and its edges along u and v.
+    fn wall(&self, face: CuboidFace) -> (Vec3, Vec3, Vec3) {
+        let (x, y, z) = (self.x, self.y, self.z);
+        let (width, height, depth) = (x.1 - x.0, y.1 - y.0, z.1 - z.0);
+        let down = Vec3::new(0.0, -height, 0.0);
+        match face {
+            CuboidFace::MinZ => (Vec3::new(x.1, y.1, z.0), Vec3::new(-width, 0.0, 0.0), down),
+            CuboidFace::MaxX => (Vec3::new(x.1, y.1, z.1), Vec3::new(0.0, 0.0, -depth), down),
+            CuboidFace::MaxZ => (Vec3::new(x.0, y.1, z.1), Vec3::new(width, 0.0, 0.0), down),
+            CuboidFace::MinX => (Vec3::new(x.0, y.1, z.0), Vec3::new(0.0, 0.0, depth), down),
+            CuboidFace::MaxY => (
+                Vec3::new(x.0, y.1, z.0),
+                Vec3::new(width, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, depth),
+            ),
+            CuboidFace::MinY => (
+                Vec3::new(x.0, y.0, z.1),
+                Vec3::new(width, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, -depth),
+            ),
         }
+    }
+
+    fn add_wall(&self, face: CuboidFace, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+        let (origin, along_u, along_v) = self.wall(face);
+        let normal = along_v.cross(along_u).normalize();
+        let (u, v) = (self.u[face as usize], self.v[face as usize]);
+        let (columns, rows) = self.subdivisions[face as usize];
+
+        let start = vertices.len() as u32;
+        for row in 0..=rows {
+            let t = row as f32 / rows as f32;
+            for column in 0..=columns {
+                let s = column as f32 / columns as f32;
+                vertices.push(Vertex::new(
+                    origin + along_u * s + along_v * t,
+                    normal,
+                    Vec2::new(u.0 + (u.1 - u.0) * s, v.0 + (v.1 - v.0) * t),
+                ));
+            }
+        }
+
+        let stride = columns + 1;
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = start + row * stride + column;
+                let (b, c, d) = (a + stride, a + stride + 1, a + 1);
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+    }
+}
+
+impl MeshBuilder for MeshBuilderCuboid {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for face in CuboidFace::ALL {
+            self.add_wall(face, &mut vertices, &mut indices);
+        }
+        let mut mesh = Mesh::new(None, vertices, indices);
+        mesh.compute_tangents();
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::handle::Handle;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_outward_normals() {
+        let cuboid = MeshBuilderCuboid::new_same_walls((-1.0, 1.0), (0.0, 2.0), (-0.5, 0.5));
+        let mesh = cuboid.build_mesh().unwrap();
+        assert_eq!(mesh.vertices.len(), 6 * 4);
+        assert_eq!(mesh.indices.len(), 6 * 6);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+
+        let expected = [
+            -Vec3::unit_z(),
+            Vec3::unit_x(),
+            Vec3::unit_z(),
+            -Vec3::unit_x(),
+            Vec3::unit_y(),
+            -Vec3::unit_y(),
+        ];
+        let center = Vec3::new(0.0, 1.0, 0.0);
+        for (triangle, corners) in mesh.indices.chunks_exact(3).enumerate() {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[corners[i] as usize]);
+            let face = (b.pos - a.pos).cross(c.pos - a.pos).normalize();
+            assert!((face - expected[triangle / 2]).magnitude() < 1e-6);
+            for corner in [a, b, c] {
+                assert!((corner.normal - face).magnitude() < 1e-6);
+            }
+            assert!(face.dot(a.pos - center) > 0.0);
+        }
+    }
 
-        self.indices.push(start_pos + 0);
-        self.indices.push(start_pos + 3);
-        self.indices.push(start_pos + 1);
+    #[test]
+    fn test_faces() {
+        let texture = Texture::new(Handle::invalid(), 0.5, 0.0, 0.5, 0.25);
+        let mesh = MeshBuilderCuboid::new_same_walls((0.0, 1.0), (0.0, 1.0), (0.0, 1.0))
+            .set_subdivisions(2, 2)
+            .set_face_texture(CuboidFace::MaxY, &texture)
+            .build_mesh()
+            .unwrap();
+        assert_eq!(mesh.vertices.len(), 6 * 3 * 3);
+        assert_eq!(mesh.indices.len(), 6 * 2 * 2 * 6);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
 
-        self.indices.push(start_pos + 1);
-        self.indices.push(start_pos + 3);
-        self.indices.push(start_pos + 2);
+        // The top starts at its back left corner, in the corner of its texture window.
+        let top = &mesh.vertices[4 * 9..5 * 9];
+        assert_eq!(top[0].pos, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(top[0].tex_coord, Vec2::new(0.5, 0.0));
+        assert_eq!(top[8].pos, Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(top[8].tex_coord, Vec2::new(1.0, 0.25));
 
-        Ok(())
+        // Mixed subdivisions only change the wall they are set on.
+        let mesh = MeshBuilderCuboid::new_same_walls((0.0, 1.0), (0.0, 1.0), (0.0, 1.0))
+            .set_face_subdivisions(CuboidFace::MaxX, 4, 1)
+            .build_mesh()
+            .unwrap();
+        assert_eq!(mesh.vertices.len(), 5 * 4 + 10);
+        assert_normals(&mesh);
     }
 }
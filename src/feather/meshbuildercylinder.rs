@@ -0,0 +1,95 @@
+use anyhow::Result;
+
+use super::math::Vec2;
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::{MeshBuilderLathe, ProfilePoint};
+use super::texture::Texture;
+
+/// Builds a closed cylinder around the Z axis, centered on the origin. The side wraps the
+/// texture once around, the caps map it flat.
+pub struct MeshBuilderCylinder {
+    radius: f32,
+    height: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderCylinder {
+    pub fn new(radius: f32, height: f32) -> Self {
+        Self {
+            radius,
+            height,
+            segments: 32,
+            rings: 1,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the Z axis, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of rings the side is divided into along the Z axis, at least 1.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(1);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderCylinder {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let half = self.height / 2.0;
+        let profile = (0..=self.rings)
+            .map(|ring| {
+                let t = ring as f32 / self.rings as f32;
+                ProfilePoint::new(
+                    self.radius,
+                    -half + t * self.height,
+                    Vec2::new(1.0, 0.0),
+                    1.0 - t,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe
+            .add_strip(&profile)
+            .add_cap(self.radius, -half, false, 1)
+            .add_cap(self.radius, half, true, 1)
+            .build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_cylinder() {
+        let mesh = MeshBuilderCylinder::new(0.5, 2.0)
+            .set_segments(6)
+            .set_rings(2)
+            .build_mesh()
+            .unwrap();
+
+        // Three rings of 6 + 1 seam vertices on the side and a center plus 6 per cap.
+        assert_eq!(mesh.vertices.len(), 3 * 7 + 2 * 7);
+        assert_eq!(mesh.indices.len(), 2 * 6 * 6 + 2 * 6 * 3);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::MeshBuilderLathe;
+use super::texture::Texture;
+
+/// Builds a flat disc in the XY plane around the origin, facing +Z. The texture is mapped flat
+/// with its center in the middle of the disc.
+pub struct MeshBuilderDisc {
+    radius: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderDisc {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            segments: 32,
+            rings: 1,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the center, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of concentric rings, at least 1.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(1);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderDisc {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe
+            .add_cap(self.radius, 0.0, true, self.rings)
+            .build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::math::Vec2;
+    use crate::feather::mesh::tests::assert_normals;
+
+    #[test]
+    fn test_disc() {
+        let mesh = MeshBuilderDisc::new(2.0)
+            .set_segments(10)
+            .set_rings(3)
+            .build_mesh()
+            .unwrap();
+
+        assert_eq!(mesh.vertices.len(), 1 + 3 * 10);
+        assert_eq!(mesh.indices.len(), 10 * 3 + 2 * 10 * 6);
+        assert_normals(&mesh);
+        // The first outer vertex lies on +X, at the right edge of the texture.
+        assert_eq!(mesh.vertices[21].tex_coord, Vec2::new(1.0, 0.5));
+    }
+}
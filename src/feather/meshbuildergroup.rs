@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Result};
+use cgmath::{InnerSpace, Matrix, Matrix3, SquareMatrix, Zero};
+
+use super::math::{Mat4, Vec4};
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+
+/// Combines the meshes of several builders into one, each placed with its own transform.
+///
+/// The parts have to share their vertex layout, which the combined mesh takes over.
+pub struct MeshBuilderGroup {
+    parts: Vec<(Box<dyn MeshBuilder>, Mat4)>,
+}
+
+impl MeshBuilderGroup {
+    pub fn new() -> Self {
+        Self { parts: Vec::new() }
+    }
+
+    /// Adds the mesh of a builder, transformed from its own space into the group's.
+    pub fn add(mut self, builder: Box<dyn MeshBuilder>, transform: Mat4) -> Self {
+        self.parts.push((builder, transform));
+        self
+    }
+}
+
+impl Default for MeshBuilderGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MeshBuilder for MeshBuilderGroup {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut layout = None;
+        for (builder, transform) in &self.parts {
+            let linear = Matrix3::from_cols(
+                transform.x.truncate(),
+                transform.y.truncate(),
+                transform.z.truncate(),
+            );
+            let normal_matrix = linear
+                .invert()
+                .ok_or(anyhow!("Mesh group transform cannot be inverted"))?
+                .transpose();
+            // A mirroring transform turns the triangles inside out unless they are flipped.
+            let mirrored = linear.determinant() < 0.0;
+
+            let mesh = builder.build_mesh()?;
+            match &layout {
+                None => layout = Some(mesh.get_layout().clone()),
+                Some(layout) if layout != mesh.get_layout() => {
+                    return Err(anyhow!(
+                        "Mesh group parts have different vertex layouts: {:?} and {:?}",
+                        layout,
+                        mesh.get_layout()
+                    ))
+                }
+                Some(_) => {}
+            }
+            let start = vertices.len() as u32;
+            vertices.extend(mesh.vertices.iter().map(|vertex| {
+                let mut vertex = *vertex;
+                vertex.pos = (transform * vertex.pos.extend(1.0)).truncate();
+                vertex.normal = (normal_matrix * vertex.normal).normalize();
+                if vertex.tangent != Vec4::zero() {
+                    let tangent = (linear * vertex.tangent.truncate()).normalize();
+                    let sign = if mirrored { -1.0 } else { 1.0 };
+                    vertex.tangent = tangent.extend(vertex.tangent.w * sign);
+                }
+                vertex
+            }));
+            for triangle in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| start + triangle[i]);
+                if mirrored {
+                    indices.extend([a, c, b]);
+                } else {
+                    indices.extend([a, b, c]);
+                }
+            }
+        }
+        let mut mesh = Mesh::new(None, vertices, indices);
+        if let Some(layout) = layout {
+            mesh.set_layout(layout);
+        }
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::math::Vec3;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+    use crate::feather::meshbuilderdisc::MeshBuilderDisc;
+    use crate::feather::meshbuilderuvsphere::MeshBuilderUvSphere;
+    use crate::feather::scene::Scene;
+    use crate::feather::vertexlayout::VertexLayout;
+
+    /// Builds a copy of another builder's mesh with a different vertex layout.
+    struct WithLayout(Box<dyn MeshBuilder>, VertexLayout);
+
+    impl MeshBuilder for WithLayout {
+        fn build_mesh(&self) -> Result<Mesh> {
+            let mut mesh = self.0.build_mesh()?;
+            mesh.set_layout(self.1.clone());
+            Ok(mesh)
+        }
+    }
+
+    #[test]
+    fn test_group() {
+        let builders: Vec<Box<dyn MeshBuilder>> = vec![
+            Box::new(MeshBuilderUvSphere::new(1.0).set_segments(8).set_rings(4)),
+            Box::new(MeshBuilderDisc::new(1.0).set_segments(8)),
+        ];
+        let meshes = builders
+            .iter()
+            .map(|b| b.build_mesh().unwrap())
+            .collect::<Vec<_>>();
+
+        // A sphere mirrored and moved up on top of the disc.
+        let group = builders
+            .into_iter()
+            .zip([
+                Mat4::from_translation(Vec3::new(0.0, 0.0, 2.0))
+                    * Mat4::from_nonuniform_scale(-1.0, 1.0, 1.0),
+                Mat4::identity(),
+            ])
+            .fold(MeshBuilderGroup::new(), |group, (builder, transform)| {
+                group.add(builder, transform)
+            });
+        let mesh = group.build_mesh().unwrap();
+        assert_eq!(
+            mesh.vertices.len(),
+            meshes[0].vertices.len() + meshes[1].vertices.len()
+        );
+        assert_eq!(
+            mesh.indices.len(),
+            meshes[0].indices.len() + meshes[1].indices.len()
+        );
+        assert_eq!(mesh.vertices[0].pos.z, 1.0);
+        assert_normals(&mesh);
+        for vertex in &mesh.vertices[..meshes[0].vertices.len()] {
+            let tangent = vertex.tangent.truncate();
+            assert!((tangent.magnitude() - 1.0).abs() < 1e-5);
+            assert!(tangent.dot(vertex.normal).abs() < 1e-5);
+        }
+
+        let sphere = Mesh::new(
+            None,
+            mesh.vertices[..meshes[0].vertices.len()].to_vec(),
+            mesh.indices[..meshes[0].indices.len()].to_vec(),
+        );
+        assert_closed(&sphere);
+
+        let mut scene = Scene::new();
+        let handle = group.build_into(&mut scene).unwrap();
+        assert_eq!(scene.get_mesh(handle).unwrap().indices, mesh.indices);
+    }
+
+    #[test]
+    fn test_group_layout() {
+        let part = |layout: VertexLayout| -> Box<dyn MeshBuilder> {
+            Box::new(WithLayout(Box::new(MeshBuilderDisc::new(1.0)), layout))
+        };
+
+        let group = MeshBuilderGroup::new()
+            .add(part(VertexLayout::position_only()), Mat4::identity())
+            .add(part(VertexLayout::position_only()), Mat4::identity());
+        let mesh = group.build_mesh().unwrap();
+        assert_eq!(mesh.get_layout(), &VertexLayout::position_only());
+
+        let group = group.add(part(VertexLayout::default()), Mat4::identity());
+        assert!(group.build_mesh().is_err());
+    }
+}
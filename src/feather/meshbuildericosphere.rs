@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use anyhow::Result;
+use cgmath::InnerSpace;
+
+use super::math::{Vec2, Vec3};
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::texture::Texture;
+use super::vertex::Vertex;
+
+/// Builds a sphere around the origin by subdividing an icosahedron, which spreads the triangles
+/// more evenly than `MeshBuilderUvSphere`. The texture is wrapped around the Z axis like on the
+/// UV sphere; vertices on the seam are duplicated.
+pub struct MeshBuilderIcosphere {
+    radius: f32,
+    subdivisions: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderIcosphere {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            subdivisions: 2,
+            texture: None,
+        }
+    }
+
+    /// Sets how often every triangle is split into four; 0 builds the icosahedron itself.
+    pub fn set_subdivisions(mut self, subdivisions: u32) -> Self {
+        self.subdivisions = subdivisions;
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Maps the corners of a triangle on the unit sphere to the texture, keeping triangles
+    /// across the seam at u = 1 from stretching over the whole texture.
+    fn triangle_uvs(corners: [Vec3; 3]) -> [Vec2; 3] {
+        let on_pole = |p: Vec3| p.x.abs() < 1e-6 && p.y.abs() < 1e-6;
+        let mut u = corners.map(|p| (p.y.atan2(p.x) / TAU).rem_euclid(1.0));
+        let others = corners.iter().zip(u).filter(|(p, _)| !on_pole(**p));
+        let (min, max) = others.fold((1.0f32, 0.0f32), |(min, max), (_, u)| {
+            (min.min(u), max.max(u))
+        });
+        if max - min > 0.5 {
+            for u in u.iter_mut().filter(|u| **u < 0.5) {
+                *u += 1.0;
+            }
+        }
+        // A pole has no longitude of its own; it takes the middle of the other two corners.
+        for i in 0..3 {
+            if on_pole(corners[i]) {
+                u[i] = (u[(i + 1) % 3] + u[(i + 2) % 3]) / 2.0;
+            }
+        }
+        [0, 1, 2].map(|i| Vec2::new(u[i], corners[i].z.clamp(-1.0, 1.0).acos() / PI))
+    }
+}
+
+impl MeshBuilder for MeshBuilderIcosphere {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let t = (1.0 + 5f32.sqrt()) / 2.0;
+        #[rustfmt::skip]
+        let mut positions = vec![
+            Vec3::new(-1.0, t, 0.0), Vec3::new(1.0, t, 0.0), Vec3::new(-1.0, -t, 0.0), Vec3::new(1.0, -t, 0.0),
+            Vec3::new(0.0, -1.0, t), Vec3::new(0.0, 1.0, t), Vec3::new(0.0, -1.0, -t), Vec3::new(0.0, 1.0, -t),
+            Vec3::new(t, 0.0, -1.0), Vec3::new(t, 0.0, 1.0), Vec3::new(-t, 0.0, -1.0), Vec3::new(-t, 0.0, 1.0),
+        ]
+        .into_iter()
+        .map(|p| p.normalize())
+        .collect::<Vec<_>>();
+        #[rustfmt::skip]
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..self.subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let p = (positions[a as usize] + positions[b as usize]).normalize();
+                    positions.push(p);
+                    positions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut unique = HashMap::new();
+        for triangle in triangles {
+            let uvs = Self::triangle_uvs(triangle.map(|i| positions[i as usize]));
+            for (index, uv) in triangle.into_iter().zip(uvs) {
+                let vertex = *unique.entry((index, uv.x.to_bits())).or_insert_with(|| {
+                    let normal = positions[index as usize];
+                    vertices.push(Vertex::new(normal * self.radius, normal, uv));
+                    vertices.len() as u32 - 1
+                });
+                indices.push(vertex);
+            }
+        }
+
+        let mut mesh = Mesh::new(None, vertices, indices);
+        mesh.compute_tangents();
+        if let Some(texture) = &self.texture {
+            mesh.remap_uvs(texture);
+        }
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_icosphere() {
+        for subdivisions in 0..3 {
+            let mesh = MeshBuilderIcosphere::new(1.5)
+                .set_subdivisions(subdivisions)
+                .build_mesh()
+                .unwrap();
+
+            // 20 * 4^n triangles over 10 * 4^n + 2 positions, some doubled along the seam.
+            let faces = 20 * 4usize.pow(subdivisions);
+            assert_eq!(mesh.indices.len(), 3 * faces);
+            assert!(mesh.vertices.len() >= faces / 2 + 2);
+            assert_closed(&mesh);
+            assert_normals(&mesh);
+            for vertex in &mesh.vertices {
+                assert!((vertex.pos.magnitude() - 1.5).abs() < 1e-5);
+                assert!((0.0..=1.5).contains(&vertex.tex_coord.x));
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::f32::consts::TAU;
+
+use anyhow::Result;
+
+use super::math::{Vec2, Vec3};
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::texture::Texture;
+use super::vertex::Vertex;
+
+/// A point of the profile a `MeshBuilderLathe` spins around the Z axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfilePoint {
+    /// The distance from the Z axis; 0 closes the surface in a pole.
+    pub radius: f32,
+    pub z: f32,
+    /// The normal in the plane of the profile, as (away from the axis, along Z).
+    pub normal: Vec2,
+    /// The texture coordinate along the profile.
+    pub v: f32,
+}
+
+impl ProfilePoint {
+    pub fn new(radius: f32, z: f32, normal: Vec2, v: f32) -> Self {
+        Self {
+            radius,
+            z,
+            normal,
+            v,
+        }
+    }
+}
+
+/// Builds surfaces of revolution around the Z axis, like spheres, cylinders and tori.
+///
+/// Strips wrap the texture once around the axis from u = 0 at +X, counter-clockwise seen from
+/// above. Profiles have to run upwards on the outside of the shape for the triangles to face
+/// outwards.
+pub struct MeshBuilderLathe {
+    segments: u32,
+    texture: Option<Texture>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilderLathe {
+    /// Creates a builder dividing the circle around the axis into `segments` steps, at least 3.
+    pub fn new(segments: u32) -> Self {
+        Self {
+            segments: segments.max(3),
+            texture: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Spins a smooth profile around the axis. Consecutive points are connected by quads, or by
+    /// triangles where one of them lies on the axis.
+    pub fn add_strip(mut self, profile: &[ProfilePoint]) -> Self {
+        let rows = profile
+            .iter()
+            .map(|point| self.add_ring(point))
+            .collect::<Vec<_>>();
+
+        for (i, pair) in profile.windows(2).enumerate() {
+            let (lower, upper) = (rows[i], rows[i + 1]);
+            for s in 0..self.segments {
+                let (a, b, c, d) = (lower + s, lower + s + 1, upper + s + 1, upper + s);
+                match (pair[0].radius == 0.0, pair[1].radius == 0.0) {
+                    (false, false) => self.indices.extend([a, b, c, a, c, d]),
+                    (true, false) => self.indices.extend([a, c, d]),
+                    (false, true) => self.indices.extend([a, b, d]),
+                    (true, true) => {}
+                }
+            }
+        }
+        self
+    }
+
+    /// Closes the surface with a flat disc of `rings` concentric rings at height `z`, facing up
+    /// or down. The texture is mapped flat, as seen from the side the disc faces.
+    pub fn add_cap(mut self, radius: f32, z: f32, up: bool, rings: u32) -> Self {
+        let rings = rings.max(1);
+        let (normal, flip) = if up {
+            (Vec3::unit_z(), 1.0)
+        } else {
+            (-Vec3::unit_z(), -1.0)
+        };
+
+        let center = self.vertices.len() as u32;
+        self.vertices.push(Vertex::new(
+            Vec3::new(0.0, 0.0, z),
+            normal,
+            Vec2::new(0.5, 0.5),
+        ));
+        for ring in 1..=rings {
+            let r = ring as f32 / rings as f32 * radius;
+            for s in 0..self.segments {
+                let (cos, sin) = self.direction(s as f32);
+                self.vertices.push(Vertex::new(
+                    Vec3::new(r * cos, r * sin, z),
+                    normal,
+                    Vec2::new(
+                        0.5 + flip * 0.5 * r * cos / radius,
+                        0.5 - 0.5 * r * sin / radius,
+                    ),
+                ));
+            }
+        }
+
+        let ring_start = |ring: u32| center + 1 + (ring - 1) * self.segments;
+        for s in 0..self.segments {
+            let next = (s + 1) % self.segments;
+            let first = ring_start(1);
+            let mut triangles = vec![[center, first + s, first + next]];
+            for ring in 2..=rings {
+                let (inner, outer) = (ring_start(ring - 1), ring_start(ring));
+                triangles.push([inner + s, outer + s, outer + next]);
+                triangles.push([inner + s, outer + next, inner + next]);
+            }
+            for [a, b, c] in triangles {
+                // Seen from below the triangles have to wind the other way round.
+                if up {
+                    self.indices.extend([a, b, c]);
+                } else {
+                    self.indices.extend([a, c, b]);
+                }
+            }
+        }
+        self
+    }
+
+    /// Returns the cosine and sine of the angle of a position around the axis, in segments.
+    fn direction(&self, segment: f32) -> (f32, f32) {
+        // The seam repeats the angle of the first segment exactly, so it closes without gaps.
+        let angle = TAU * (segment % self.segments as f32) / self.segments as f32;
+        (angle.cos(), angle.sin())
+    }
+
+    /// Adds the vertices of one profile point and returns the index of the first. Points on the
+    /// axis get one vertex per segment, at its middle; all others one per segment boundary.
+    fn add_ring(&mut self, point: &ProfilePoint) -> u32 {
+        let start = self.vertices.len() as u32;
+        let (count, offset) = match point.radius == 0.0 {
+            true => (self.segments, 0.5),
+            false => (self.segments + 1, 0.0),
+        };
+        for s in 0..count {
+            let segment = s as f32 + offset;
+            let (cos, sin) = self.direction(segment);
+            self.vertices.push(Vertex::new(
+                Vec3::new(point.radius * cos, point.radius * sin, point.z),
+                Vec3::new(point.normal.x * cos, point.normal.x * sin, point.normal.y),
+                Vec2::new(segment / self.segments as f32, point.v),
+            ));
+        }
+        start
+    }
+}
+
+impl MeshBuilder for MeshBuilderLathe {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut mesh = Mesh::new(None, self.vertices.clone(), self.indices.clone());
+        mesh.compute_tangents();
+        if let Some(texture) = &self.texture {
+            mesh.remap_uvs(texture);
+        }
+        Ok(mesh)
+    }
+}
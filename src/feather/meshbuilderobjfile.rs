@@ -1,29 +1,49 @@
-use std::{collections::HashMap, fs::File, io::BufReader};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
 
 use super::{
     math::{Vec2, Vec3},
-    mesh::Mesh,
-    scene::Scene,
+    mesh::{Mesh, NormalMode},
+    meshbuilder::MeshBuilder,
+    texture::Texture,
     vertex::Vertex,
 };
 use anyhow::Result;
 
 pub struct MeshBuilderObjFile {
     file_name: String,
+    texture: Option<Texture>,
+    normal_mode: NormalMode,
 }
 
 impl MeshBuilderObjFile {
     pub fn new(file_name: &str) -> Self {
         Self {
             file_name: file_name.to_string(),
+            texture: None,
+            normal_mode: NormalMode::AngleThreshold(30.0),
         }
     }
 
-    pub fn build(self, scene: &mut Scene) -> Result<usize> {
-        let mut reader = BufReader::new(File::open(&self.file_name)?);
+    /// Remaps the texture coordinates of the model into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Sets how normals are computed for models without `vn` lines; by default edges sharper
+    /// than 30 degrees stay hard.
+    pub fn set_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.normal_mode = normal_mode;
+        self
+    }
 
+    fn load_mesh(&self, reader: &mut impl BufRead) -> Result<Mesh> {
         let (models, _) = tobj::load_obj_buf(
-            &mut reader,
+            reader,
             &tobj::LoadOptions {
                 triangulate: true,
                 single_index: true,
@@ -34,45 +54,126 @@ impl MeshBuilderObjFile {
 
         // Vertices / Indices
 
-        let mut unique_vertices = HashMap::new();
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
 
         for model in &models {
+            let has_normals = !model.mesh.normals.is_empty();
+            let has_tex_coords = !model.mesh.texcoords.is_empty();
+
+            let mut unique_vertices = HashMap::new();
+            let mut part = Mesh::new(None, Vec::new(), Vec::new());
             for index in &model.mesh.indices {
                 let pos_offset = (3 * index) as usize;
                 let tex_coord_offset = (2 * index) as usize;
                 let normals_offset = (3 * index) as usize;
 
-                let vertex = Vertex::new(
-                    Vec3 {
-                        x: model.mesh.positions[pos_offset],
-                        y: model.mesh.positions[pos_offset + 1],
-                        z: model.mesh.positions[pos_offset + 2],
-                    },
-                    Vec3 {
+                let normal = match has_normals {
+                    true => Vec3 {
                         x: model.mesh.normals[normals_offset],
                         y: model.mesh.normals[normals_offset + 1],
                         z: model.mesh.normals[normals_offset + 2],
                     },
-                    Vec2 {
+                    false => Vec3::new(0.0, 0.0, 0.0),
+                };
+                let tex_coord = match has_tex_coords {
+                    true => Vec2 {
                         x: model.mesh.texcoords[tex_coord_offset],
                         y: 1.0 - model.mesh.texcoords[tex_coord_offset + 1],
                     },
+                    false => Vec2::new(0.0, 0.0),
+                };
+                let vertex = Vertex::new(
+                    Vec3 {
+                        x: model.mesh.positions[pos_offset],
+                        y: model.mesh.positions[pos_offset + 1],
+                        z: model.mesh.positions[pos_offset + 2],
+                    },
+                    normal,
+                    tex_coord,
                 );
 
                 if let Some(index) = unique_vertices.get(&vertex) {
-                    indices.push(*index as u32);
+                    part.indices.push(*index as u32);
                 } else {
-                    let index = vertices.len();
+                    let index = part.vertices.len();
                     unique_vertices.insert(vertex, index);
-                    vertices.push(vertex);
-                    indices.push(index as u32);
+                    part.vertices.push(vertex);
+                    part.indices.push(index as u32);
                 }
             }
+
+            // Normals are computed per model, so the given normals of other models are kept and
+            // separate models are never smoothed together.
+            if !has_normals {
+                part.compute_normals(self.normal_mode);
+            }
+            let offset = vertices.len() as u32;
+            vertices.extend(part.vertices);
+            indices.extend(part.indices.iter().map(|i| i + offset));
+        }
+
+        let mut mesh = Mesh::new(None, vertices, indices);
+        // OBJ files have no tangents.
+        mesh.compute_tangents();
+        if let Some(texture) = &self.texture {
+            mesh.remap_uvs(texture);
+        }
+        Ok(mesh)
+    }
+}
+
+impl MeshBuilder for MeshBuilderObjFile {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut reader = BufReader::new(File::open(&self.file_name)?);
+        self.load_mesh(&mut reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::InnerSpace;
+
+    use super::*;
+    use crate::feather::mesh::tests::assert_normals;
+
+    #[test]
+    fn test_missing_normals() {
+        // A unit cube without normals or texture coordinates.
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n\
+                   f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6\n";
+        let builder = MeshBuilderObjFile::new("cube.obj");
+        let mesh = builder.load_mesh(&mut obj.as_bytes()).unwrap();
+
+        // Every corner is split into three vertices with the normals of its walls.
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        assert_normals(&mesh);
+        for vertex in &mesh.vertices {
+            assert!((vertex.tangent.truncate().magnitude() - 1.0).abs() < 1e-6);
+            assert!(vertex.tangent.truncate().dot(vertex.normal).abs() < 1e-6);
         }
 
-        let mesh = Mesh::new(None, vertices, indices);
-        Ok(scene.add_mesh(mesh))
+        let smooth = builder.set_normal_mode(NormalMode::Smooth);
+        let mesh = smooth.load_mesh(&mut obj.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 8);
+
+        // Two squares sharing an edge, of which only the first has normals. Those are kept
+        // although they do not match its winding, and the second gets its own normals.
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\nvn 0 1 0\n\
+                   o a\nf 1//1 2//1 3//1 4//1\n\
+                   o b\nf 2 5 6 3\n";
+        let mesh = smooth.load_mesh(&mut obj.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.indices.len(), 12);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let expected = match triangle[0] < 4 {
+                true => Vec3::unit_y(),
+                false => Vec3::unit_z(),
+            };
+            for index in triangle {
+                assert_eq!(mesh.vertices[*index as usize].normal, expected);
+            }
+        }
     }
 }
@@ -0,0 +1,99 @@
+use anyhow::Result;
+
+use super::math::{Vec2, Vec3};
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::texture::Texture;
+use super::vertex::Vertex;
+
+/// Builds a flat rectangle in the XY plane around the origin, facing +Z, optionally divided
+/// into a grid of quads. The texture covers it once, with v = 0 along the +Y edge.
+pub struct MeshBuilderPlane {
+    width: f32,
+    depth: f32,
+    columns: u32,
+    rows: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderPlane {
+    /// Creates a plane `width` long along X and `depth` long along Y.
+    pub fn new(width: f32, depth: f32) -> Self {
+        Self {
+            width,
+            depth,
+            columns: 1,
+            rows: 1,
+            texture: None,
+        }
+    }
+
+    /// Divides the plane into `columns` quads along X and `rows` along Y, at least 1 each.
+    pub fn set_subdivisions(mut self, columns: u32, rows: u32) -> Self {
+        self.columns = columns.max(1);
+        self.rows = rows.max(1);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderPlane {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let mut vertices = Vec::new();
+        for row in 0..=self.rows {
+            let v = row as f32 / self.rows as f32;
+            for column in 0..=self.columns {
+                let u = column as f32 / self.columns as f32;
+                vertices.push(Vertex::new(
+                    Vec3::new((u - 0.5) * self.width, (v - 0.5) * self.depth, 0.0),
+                    Vec3::unit_z(),
+                    Vec2::new(u, 1.0 - v),
+                ));
+            }
+        }
+
+        let mut indices = Vec::new();
+        let stride = self.columns + 1;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let a = row * stride + column;
+                let (b, c, d) = (a + 1, a + stride + 1, a + stride);
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+
+        let mut mesh = Mesh::new(None, vertices, indices);
+        mesh.compute_tangents();
+        if let Some(texture) = &self.texture {
+            mesh.remap_uvs(texture);
+        }
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::assert_normals;
+
+    #[test]
+    fn test_plane() {
+        let mesh = MeshBuilderPlane::new(4.0, 2.0)
+            .set_subdivisions(4, 2)
+            .build_mesh()
+            .unwrap();
+
+        assert_eq!(mesh.vertices.len(), 5 * 3);
+        assert_eq!(mesh.indices.len(), 4 * 2 * 6);
+        assert_normals(&mesh);
+
+        let last = mesh.vertices.last().unwrap();
+        assert_eq!(last.pos, Vec3::new(2.0, 1.0, 0.0));
+        assert_eq!(last.tex_coord, Vec2::new(1.0, 0.0));
+    }
+}
@@ -0,0 +1,94 @@
+use std::f32::consts::TAU;
+
+use anyhow::Result;
+
+use super::math::Vec2;
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::{MeshBuilderLathe, ProfilePoint};
+use super::texture::Texture;
+
+/// Builds a torus around the Z axis, centered on the origin. The texture is wrapped once
+/// around the axis and once around the tube, starting on its outside.
+pub struct MeshBuilderTorus {
+    radius: f32,
+    tube_radius: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderTorus {
+    /// Creates a torus whose tube of radius `tube_radius` circles the axis at `radius`.
+    pub fn new(radius: f32, tube_radius: f32) -> Self {
+        Self {
+            radius,
+            tube_radius,
+            segments: 32,
+            rings: 16,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the Z axis, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of rings around the tube, at least 3.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(3);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderTorus {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let profile = (0..=self.rings)
+            .map(|ring| {
+                // The last ring repeats the first exactly to close the tube.
+                let angle = TAU * (ring % self.rings) as f32 / self.rings as f32;
+                let (sin, cos) = angle.sin_cos();
+                ProfilePoint::new(
+                    self.radius + self.tube_radius * cos,
+                    self.tube_radius * sin,
+                    Vec2::new(cos, sin),
+                    ring as f32 / self.rings as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe.add_strip(&profile).build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_torus() {
+        let mesh = MeshBuilderTorus::new(1.0, 0.25)
+            .set_segments(12)
+            .set_rings(6)
+            .build_mesh()
+            .unwrap();
+
+        assert_eq!(mesh.vertices.len(), 7 * 13);
+        assert_eq!(mesh.indices.len(), 6 * 12 * 6);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+    }
+}
@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+
+use anyhow::Result;
+
+use super::math::Vec2;
+use super::mesh::Mesh;
+use super::meshbuilder::MeshBuilder;
+use super::meshbuilderlathe::{MeshBuilderLathe, ProfilePoint};
+use super::texture::Texture;
+
+/// Builds a sphere around the origin from rings of latitude and segments of longitude, with the
+/// poles on the Z axis. The texture is wrapped around once, with v = 0 at the top pole.
+pub struct MeshBuilderUvSphere {
+    radius: f32,
+    segments: u32,
+    rings: u32,
+    texture: Option<Texture>,
+}
+
+impl MeshBuilderUvSphere {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            segments: 32,
+            rings: 16,
+            texture: None,
+        }
+    }
+
+    /// Sets the number of segments around the Z axis, at least 3.
+    pub fn set_segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Sets the number of rings from pole to pole, at least 2.
+    pub fn set_rings(mut self, rings: u32) -> Self {
+        self.rings = rings.max(2);
+        self
+    }
+
+    /// Remaps the texture coordinates into the window of an atlas texture.
+    pub fn set_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+impl MeshBuilder for MeshBuilderUvSphere {
+    fn build_mesh(&self) -> Result<Mesh> {
+        let profile = (0..=self.rings)
+            .map(|ring| {
+                let t = ring as f32 / self.rings as f32;
+                let angle = PI * t - PI / 2.0;
+                // The poles lie exactly on the axis.
+                let (sin, cos) = if ring == 0 || ring == self.rings {
+                    (angle.signum(), 0.0)
+                } else {
+                    angle.sin_cos()
+                };
+                ProfilePoint::new(
+                    self.radius * cos,
+                    self.radius * sin,
+                    Vec2::new(cos, sin),
+                    1.0 - t,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lathe = MeshBuilderLathe::new(self.segments);
+        if let Some(texture) = self.texture {
+            lathe = lathe.set_texture(texture);
+        }
+        lathe.add_strip(&profile).build_mesh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::mesh::tests::{assert_closed, assert_normals};
+
+    #[test]
+    fn test_uv_sphere() {
+        let mesh = MeshBuilderUvSphere::new(2.0)
+            .set_segments(8)
+            .set_rings(4)
+            .build_mesh()
+            .unwrap();
+
+        // Three rings of 8 + 1 seam vertices and one vertex per segment at each pole.
+        assert_eq!(mesh.vertices.len(), 3 * 9 + 2 * 8);
+        assert_eq!(mesh.indices.len(), 8 * 3 * 6);
+        assert_closed(&mesh);
+        assert_normals(&mesh);
+    }
+}
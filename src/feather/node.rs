@@ -1,21 +1,26 @@
-use std::collections::HashSet;
+use anyhow::{anyhow, Result};
+use cgmath::{One, SquareMatrix};
 
-use cgmath::SquareMatrix;
-
-use super::math::Mat4;
+use super::handle::Handle;
+use super::material::Material;
+use super::math::{Mat4, Quat, Vec3};
+use super::mesh::Mesh;
 use super::object::Object;
 
 pub struct Node {
     name: Option<String>,
-    handle: usize,
-    parent: Option<usize>,
-    childreen: HashSet<usize>,
+    handle: Handle<Node>,
+    parent: Option<Handle<Node>>,
+    childreen: Vec<Handle<Node>>,
+    translation: Vec3,
+    rotation: Quat,
+    scale: Vec3,
     transform: Mat4,
     global_transform: Option<Mat4>,
     transparent: bool,
     visible: bool,
-    mesh: Option<usize>,
-    material: Option<usize>,
+    mesh: Option<Handle<Mesh>>,
+    material: Option<Handle<Material>>,
 }
 
 impl Object for Node {
@@ -23,22 +28,33 @@ impl Object for Node {
         self.name.clone()
     }
 
-    fn set_handle(&mut self, handle: usize) {
+    fn has_name(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name)
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+    }
+
+    fn set_handle(&mut self, handle: Handle<Self>) {
         self.handle = handle;
     }
 
-    fn get_handle(&self) -> usize {
+    fn get_handle(&self) -> Handle<Self> {
         self.handle
     }
 }
 
 impl Node {
-    fn internal_new(name: Option<String>, parent: Option<usize>, transparent: bool) -> Self {
+    fn internal_new(name: Option<String>, parent: Option<Handle<Node>>, transparent: bool) -> Self {
         Self {
             name,
-            handle: usize::MAX,
-            parent: parent,
-            childreen: HashSet::new(),
+            handle: Handle::invalid(),
+            parent,
+            childreen: Vec::new(),
+            translation: Vec3::new(0.0, 0.0, 0.0),
+            rotation: Quat::one(),
+            scale: Vec3::new(1.0, 1.0, 1.0),
             transform: Mat4::identity(),
             global_transform: None,
             transparent,
@@ -52,11 +68,15 @@ impl Node {
         Self::internal_new(name, None, false)
     }
 
-    pub fn new_with_transparency(name: Option<String>, parent: usize, transparent: bool) -> Self {
+    pub fn new_with_transparency(
+        name: Option<String>,
+        parent: Handle<Node>,
+        transparent: bool,
+    ) -> Self {
         Self::internal_new(name, Some(parent), transparent)
     }
 
-    pub fn new(name: Option<String>, parent: usize) -> Self {
+    pub fn new(name: Option<String>, parent: Handle<Node>) -> Self {
         Self::internal_new(name, Some(parent), false)
     }
 
@@ -64,35 +84,186 @@ impl Node {
         self.parent.is_none()
     }
 
-    pub fn add_child(&mut self, child: usize) {
-        self.childreen.insert(child);
+    /// Appends the child after the existing childreen.
+    pub fn add_child(&mut self, child: Handle<Node>) {
+        if !self.childreen.contains(&child) {
+            self.childreen.push(child);
+        }
     }
 
-    pub fn remove_child(&mut self, child: usize) {
-        self.childreen.remove(&child);
+    pub fn remove_child(&mut self, child: Handle<Node>) {
+        self.childreen.retain(|c| *c != child);
     }
 
-    pub fn get_childreen(&self) -> &HashSet<usize> {
+    /// Returns the childreen in their drawing and traversal order.
+    pub fn get_childreen(&self) -> &[Handle<Node>] {
         &self.childreen
     }
 
-    pub fn set_parent(&mut self, parent: Option<usize>) {
+    pub(crate) fn get_childreen_mut(&mut self) -> &mut Vec<Handle<Node>> {
+        &mut self.childreen
+    }
+
+    pub fn get_child_index(&self, child: Handle<Node>) -> Option<usize> {
+        self.childreen.iter().position(|c| *c == child)
+    }
+
+    pub fn set_child_index(&mut self, child: Handle<Node>, index: usize) -> Result<()> {
+        set_node_index(&mut self.childreen, child, index)
+    }
+
+    pub fn move_child_before(&mut self, child: Handle<Node>, sibling: Handle<Node>) -> Result<()> {
+        move_node_next_to(&mut self.childreen, child, sibling, 0)
+    }
+
+    pub fn move_child_after(&mut self, child: Handle<Node>, sibling: Handle<Node>) -> Result<()> {
+        move_node_next_to(&mut self.childreen, child, sibling, 1)
+    }
+
+    pub fn set_parent(&mut self, parent: Option<Handle<Node>>) {
         self.parent = parent;
     }
 
-    pub fn get_parent(&self) -> Option<usize> {
+    pub fn get_parent(&self) -> Option<Handle<Node>> {
         self.parent
     }
 
-    pub fn set_mesh(&mut self, mesh: usize) {
+    pub fn set_mesh(&mut self, mesh: Handle<Mesh>) {
         self.mesh = Some(mesh);
     }
 
-    pub fn get_mesh(&self) -> Option<usize> {
+    pub fn get_mesh(&self) -> Option<Handle<Mesh>> {
         self.mesh
     }
 
     pub fn remove_mesh(&mut self) {
         self.mesh = None;
     }
+
+    /// Sets the material the mesh of the node is drawn with.
+    pub fn set_material(&mut self, material: Handle<Material>) {
+        self.material = Some(material);
+    }
+
+    /// Returns the material of the node; nodes without one use the default material.
+    pub fn get_material(&self) -> Option<Handle<Material>> {
+        self.material
+    }
+
+    pub fn remove_material(&mut self) {
+        self.material = None;
+    }
+
+    /// Hides or shows the node together with its whole subtree.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Returns the node's own visibility flag; see `Scene::is_node_visible` for the inherited one.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Transparent nodes are drawn after the opaque ones, back to front, with alpha blending.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    pub fn set_translation(&mut self, translation: Vec3) {
+        self.translation = translation;
+        self.update_transform();
+    }
+
+    pub fn get_translation(&self) -> Vec3 {
+        self.translation
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quat) {
+        self.rotation = rotation;
+        self.update_transform();
+    }
+
+    pub fn get_rotation(&self) -> Quat {
+        self.rotation
+    }
+
+    pub fn set_scale(&mut self, scale: Vec3) {
+        self.scale = scale;
+        self.update_transform();
+    }
+
+    pub fn get_scale(&self) -> Vec3 {
+        self.scale
+    }
+
+    /// Returns the local transform (translation * rotation * scale) relative to the parent.
+    pub fn get_transform(&self) -> Mat4 {
+        self.transform
+    }
+
+    /// Returns the world transform computed by `Scene::update_transforms`, or `None` if the
+    /// node was changed since the last update.
+    pub fn get_global_transform(&self) -> Option<Mat4> {
+        self.global_transform
+    }
+
+    pub(crate) fn set_global_transform(&mut self, global_transform: Mat4) {
+        self.global_transform = Some(global_transform);
+    }
+
+    pub(crate) fn invalidate_global_transform(&mut self) {
+        self.global_transform = None;
+    }
+
+    fn update_transform(&mut self) {
+        self.transform = Mat4::from_translation(self.translation)
+            * Mat4::from(self.rotation)
+            * Mat4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
+        self.global_transform = None;
+    }
+}
+
+/// Moves a node to the given position in an ordered list of siblings.
+pub(crate) fn set_node_index(
+    nodes: &mut Vec<Handle<Node>>,
+    node: Handle<Node>,
+    index: usize,
+) -> Result<()> {
+    if index >= nodes.len() {
+        return Err(anyhow!("Node index out of range"));
+    }
+    let current = nodes
+        .iter()
+        .position(|n| *n == node)
+        .ok_or(anyhow!("Node not found among its siblings"))?;
+    let node = nodes.remove(current);
+    nodes.insert(index, node);
+    Ok(())
+}
+
+/// Moves a node in front of (offset 0) or behind (offset 1) a sibling in an ordered list.
+pub(crate) fn move_node_next_to(
+    nodes: &mut Vec<Handle<Node>>,
+    node: Handle<Node>,
+    sibling: Handle<Node>,
+    offset: usize,
+) -> Result<()> {
+    if node == sibling {
+        return Ok(());
+    }
+    let current = nodes
+        .iter()
+        .position(|n| *n == node)
+        .ok_or(anyhow!("Node not found among its siblings"))?;
+    if !nodes.contains(&sibling) {
+        return Err(anyhow!("Sibling not found"));
+    }
+    let node = nodes.remove(current);
+    let sibling_index = nodes.iter().position(|n| *n == sibling).unwrap();
+    nodes.insert(sibling_index + offset, node);
+    Ok(())
 }
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use super::handle::Handle;
+use super::math::Mat4;
+use super::node::Node;
+use super::scene::Scene;
+
+/// An entry yielded by the node iterators: the node, its depth below the starting nodes and
+/// its world transform.
+pub type NodeIterItem = (Handle<Node>, usize, Mat4);
+
+/// Pre-order depth-first traversal of a `Scene` node hierarchy.
+pub struct DepthFirstNodeIter<'a> {
+    scene: &'a Scene,
+    stack: Vec<NodeIterItem>,
+    visible_only: bool,
+}
+
+impl<'a> DepthFirstNodeIter<'a> {
+    pub(crate) fn new(scene: &'a Scene, start: Vec<(Handle<Node>, Mat4)>) -> Self {
+        let stack = start
+            .into_iter()
+            .rev()
+            .filter_map(|(handle, parent_transform)| {
+                let node = scene.get_node(handle)?;
+                Some((handle, 0, parent_transform * node.get_transform()))
+            })
+            .collect();
+        Self {
+            scene,
+            stack,
+            visible_only: false,
+        }
+    }
+
+    /// Skips hidden nodes together with their subtrees.
+    pub fn visible_only(mut self) -> Self {
+        self.visible_only = true;
+        self
+    }
+}
+
+impl Iterator for DepthFirstNodeIter<'_> {
+    type Item = NodeIterItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (handle, depth, transform) = loop {
+            let (handle, depth, transform) = self.stack.pop()?;
+            // Childreen of hidden nodes are never pushed, so only the starting nodes need
+            // their ancestors checked.
+            let visible = match depth {
+                0 => self.scene.is_node_visible(handle),
+                _ => self.scene.get_node(handle).is_some_and(|n| n.is_visible()),
+            };
+            if !self.visible_only || visible {
+                break (handle, depth, transform);
+            }
+        };
+        if let Some(node) = self.scene.get_node(handle) {
+            for child in node.get_childreen().iter().rev() {
+                if let Some(child_node) = self.scene.get_node(*child) {
+                    let child_transform = transform * child_node.get_transform();
+                    self.stack.push((*child, depth + 1, child_transform));
+                }
+            }
+        }
+        Some((handle, depth, transform))
+    }
+}
+
+/// Level-order breadth-first traversal of a `Scene` node hierarchy.
+pub struct BreadthFirstNodeIter<'a> {
+    scene: &'a Scene,
+    queue: VecDeque<NodeIterItem>,
+}
+
+impl<'a> BreadthFirstNodeIter<'a> {
+    pub(crate) fn new(scene: &'a Scene, start: Vec<(Handle<Node>, Mat4)>) -> Self {
+        let queue = start
+            .into_iter()
+            .filter_map(|(handle, parent_transform)| {
+                let node = scene.get_node(handle)?;
+                Some((handle, 0, parent_transform * node.get_transform()))
+            })
+            .collect();
+        Self { scene, queue }
+    }
+}
+
+impl Iterator for BreadthFirstNodeIter<'_> {
+    type Item = NodeIterItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (handle, depth, transform) = self.queue.pop_front()?;
+        if let Some(node) = self.scene.get_node(handle) {
+            for child in node.get_childreen() {
+                if let Some(child_node) = self.scene.get_node(*child) {
+                    let child_transform = transform * child_node.get_transform();
+                    self.queue.push_back((*child, depth + 1, child_transform));
+                }
+            }
+        }
+        Some((handle, depth, transform))
+    }
+}
@@ -1,75 +1,168 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 
+use thiserror::Error;
+
+use super::handle::Handle;
 use super::object::Object;
 
+/// Source of unique database ids; `0` is reserved for `Handle::invalid`.
+static NEXT_DB_ID: AtomicU32 = AtomicU32::new(1);
+
+/// What an `ObjDB` does when an object gets a name which is already in use.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NamePolicy {
+    /// The operation fails with `ObjDBError::DuplicateName`.
+    Reject,
+    /// The object holding the name is removed, so its handles become stale. It is kept until
+    /// `take_replaced` hands it out for releasing its resources.
+    Replace,
+    /// A numeric suffix is appended to the new name ("Room" becomes "Room.001").
+    #[default]
+    AutoSuffix,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ObjDBError {
+    #[error("Handle is stale or belongs to another database.")]
+    InvalidHandle,
+    #[error("Name `{0}` is already in use.")]
+    DuplicateName(String),
+}
+
 pub struct ObjDB<T: Object> {
+    id: u32,
+    name_policy: NamePolicy,
     free_indexes: Vec<usize>,
     objects: Vec<Option<T>>,
+    generations: Vec<u32>,
+    // The name each slot is registered under in `object_names`.
+    slot_names: Vec<Option<String>>,
     object_names: HashMap<String, usize>,
+    // Slots handed out mutably since the last sync, so their names may differ from `slot_names`.
+    dirty_slots: Vec<usize>,
+    slot_dirty: Vec<bool>,
+    replaced: Vec<T>,
 }
 
 impl<T: Object> ObjDB<T> {
     pub fn new() -> Self {
+        Self::with_name_policy(NamePolicy::default())
+    }
+
+    pub fn with_name_policy(name_policy: NamePolicy) -> Self {
         Self {
+            id: NEXT_DB_ID.fetch_add(1, Ordering::Relaxed),
+            name_policy,
             free_indexes: Vec::new(),
             objects: Vec::new(),
+            generations: Vec::new(),
+            slot_names: Vec::new(),
             object_names: HashMap::new(),
+            dirty_slots: Vec::new(),
+            slot_dirty: Vec::new(),
+            replaced: Vec::new(),
         }
     }
 
-    pub fn add(&mut self, mut object: T) -> usize {
-        let handle = if let Some(name) = object.get_name() {
-            if self.object_names.contains_key(&name) {
-                *self.object_names.get(&name).unwrap()
-            } else {
-                self.free_indexes.pop().unwrap_or(self.objects.len())
+    pub fn get_name_policy(&self) -> NamePolicy {
+        self.name_policy
+    }
+
+    pub fn add(&mut self, mut object: T) -> Result<Handle<T>, ObjDBError> {
+        self.sync_names();
+
+        let mut name = object.get_name();
+        let mut replaced_index = None;
+        if let Some(taken) = name.as_ref().filter(|n| self.object_names.contains_key(*n)) {
+            match self.name_policy {
+                NamePolicy::Reject => return Err(ObjDBError::DuplicateName(taken.clone())),
+                NamePolicy::Replace => replaced_index = self.object_names.get(taken).copied(),
+                NamePolicy::AutoSuffix => {
+                    name = Some(self.unique_name(taken));
+                    object.set_name(name.clone());
+                }
             }
-        } else if !self.free_indexes.is_empty() {
-            self.free_indexes.pop().unwrap()
-        } else {
-            self.objects.len()
-        };
-        if let Some(name) = object.get_name() {
-            self.object_names.insert(name, handle);
         }
-        object.set_handle(handle);
-        if handle >= self.objects.len() {
-            self.objects.push(Some(object));
-        } else {
-            self.objects[handle] = Some(object);
+
+        if let Some(replaced) = replaced_index {
+            self.replace_index(replaced);
+        }
+
+        let index = self.free_indexes.pop().unwrap_or(self.objects.len());
+        if index >= self.objects.len() {
+            self.objects.push(None);
+            self.generations.push(0);
+            self.slot_names.push(None);
+            self.slot_dirty.push(false);
+        }
+        if let Some(name) = name.clone() {
+            self.object_names.insert(name, index);
         }
+        self.slot_names[index] = name;
 
-        handle
+        let handle = Handle::new(self.id, index, self.generations[index]);
+        object.set_handle(handle);
+        self.objects[index] = Some(object);
+
+        Ok(handle)
     }
 
-    pub fn remove(&mut self, handle: usize) {
-        if let Some(object) = self.objects[handle].take() {
-            self.objects[handle] = None;
-            self.free_indexes.push(handle);
-            if let Some(name) = object.get_name() {
-                self.object_names.remove(&name);
-            }
-        }
+    /// Removes the object and returns it, or `None` if the handle is stale or foreign.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.sync_names();
+        let index = self.resolve(handle)?;
+        self.remove_index(index)
+    }
+
+    /// Renames the object, resolving a name collision according to the name policy.
+    pub fn rename(
+        &mut self,
+        handle: Handle<T>,
+        new_name: Option<String>,
+    ) -> Result<(), ObjDBError> {
+        self.sync_names();
+        let index = self.resolve(handle).ok_or(ObjDBError::InvalidHandle)?;
+        self.register_name(index, new_name)
+    }
+
+    /// Returns the objects removed by the `Replace` name policy since the last call, so their
+    /// resources can be released.
+    pub fn take_replaced(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.replaced)
+    }
+
+    /// Returns the handle of the object with the given name.
+    pub fn handle_of(&self, name: &str) -> Option<Handle<T>> {
+        self.index_of(name).map(|index| self.handle_at(index))
     }
 
-    pub fn get(&self, handle: usize) -> Option<&T> {
-        self.objects[handle].as_ref()
+    /// Returns `true` if the handle points to a live object of this database.
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        let index = self.resolve(handle)?;
+        self.objects[index].as_ref()
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&T> {
-        self.object_names
-            .get(name)
-            .and_then(|&handle| self.objects[handle].as_ref())
+        self.index_of(name)
+            .and_then(|index| self.objects[index].as_ref())
     }
 
     pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut T> {
-        self.object_names
-            .get(name)
-            .and_then(|&handle| self.objects[handle].as_mut())
+        self.sync_names();
+        let index = *self.object_names.get(name)?;
+        self.mark_dirty(index);
+        self.objects[index].as_mut()
     }
 
-    pub fn get_mut(&mut self, handle: usize) -> Option<&mut T> {
-        self.objects[handle].as_mut()
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        let index = self.resolve(handle)?;
+        self.mark_dirty(index);
+        self.objects[index].as_mut()
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &T> {
@@ -79,6 +172,135 @@ impl<T: Object> ObjDB<T> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.into_iter()
     }
+
+    /// Brings the name index up to date with names changed through `get_mut` or `iter_mut`.
+    ///
+    /// This happens automatically before any name based modification; lookups by name stay
+    /// correct without it, but have to check every object handed out since.
+    pub fn sync_names(&mut self) {
+        let dirty_slots = std::mem::take(&mut self.dirty_slots);
+        for index in &dirty_slots {
+            self.slot_dirty[*index] = false;
+        }
+        for index in dirty_slots {
+            let Some(object) = self.objects[index].as_ref() else {
+                continue;
+            };
+            let unchanged = match &self.slot_names[index] {
+                Some(name) => object.has_name(name),
+                None => object.get_name().is_none(),
+            };
+            if unchanged {
+                continue;
+            }
+            let name = object.get_name();
+            if let Err(error) = self.register_name(index, name) {
+                log::warn!("{} Reverting the name change.", error);
+                let old_name = self.slot_names[index].clone();
+                if let Some(object) = self.objects[index].as_mut() {
+                    object.set_name(old_name);
+                }
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        if !self.slot_dirty[index] {
+            self.slot_dirty[index] = true;
+            self.dirty_slots.push(index);
+        }
+    }
+
+    fn register_name(&mut self, index: usize, mut name: Option<String>) -> Result<(), ObjDBError> {
+        if let Some(taken) = name.as_ref() {
+            match self.object_names.get(taken).copied() {
+                Some(other) if other != index => match self.name_policy {
+                    NamePolicy::Reject => return Err(ObjDBError::DuplicateName(taken.clone())),
+                    NamePolicy::Replace => self.replace_index(other),
+                    NamePolicy::AutoSuffix => name = Some(self.unique_name(taken)),
+                },
+                _ => {}
+            }
+        }
+
+        if let Some(old_name) = self.slot_names[index].take() {
+            self.object_names.remove(&old_name);
+        }
+        if let Some(name) = name.clone() {
+            self.object_names.insert(name, index);
+        }
+        self.slot_names[index] = name.clone();
+        if let Some(object) = self.objects[index].as_mut() {
+            if object.get_name() != name {
+                object.set_name(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_index(&mut self, index: usize) -> Option<T> {
+        let object = self.objects[index].take()?;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free_indexes.push(index);
+        if let Some(name) = self.slot_names[index].take() {
+            self.object_names.remove(&name);
+        }
+        Some(object)
+    }
+
+    fn replace_index(&mut self, index: usize) {
+        if let Some(object) = self.remove_index(index) {
+            log::debug!(
+                "Replaced object `{}`.",
+                object.get_name().unwrap_or_default()
+            );
+            self.replaced.push(object);
+        }
+    }
+
+    fn unique_name(&self, name: &str) -> String {
+        let base = match name.rsplit_once('.') {
+            Some((base, suffix))
+                if suffix.len() >= 3 && suffix.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                base
+            }
+            _ => name,
+        };
+        (1..)
+            .map(|n| format!("{}.{:03}", base, n))
+            .find(|candidate| self.index_of(candidate).is_none())
+            .unwrap()
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        // Only slots handed out mutably can have a name the index does not know about yet.
+        let is_named = |index: &usize| {
+            self.objects[*index]
+                .as_ref()
+                .is_some_and(|o| o.has_name(name))
+        };
+        match self.object_names.get(name) {
+            Some(index) if !self.slot_dirty[*index] || is_named(index) => Some(*index),
+            _ => self.dirty_slots.iter().copied().find(is_named),
+        }
+    }
+
+    fn handle_at(&self, index: usize) -> Handle<T> {
+        Handle::new(self.id, index, self.generations[index])
+    }
+
+    fn resolve(&self, handle: Handle<T>) -> Option<usize> {
+        let index = handle.get_index();
+        if handle.get_db_id() == self.id
+            && index < self.objects.len()
+            && self.generations[index] == handle.get_generation()
+        {
+            Some(index)
+        } else {
+            None
+        }
+    }
 }
 
 impl<T: Object> Default for ObjDB<T> {
@@ -110,6 +332,9 @@ impl<'a, T: Object> IntoIterator for &'a mut ObjDB<T> {
     type IntoIter = std::iter::Flatten<std::slice::IterMut<'a, Option<T>>>;
 
     fn into_iter(self) -> Self::IntoIter {
+        for index in 0..self.objects.len() {
+            self.mark_dirty(index);
+        }
         self.objects.iter_mut().flatten()
     }
 }
@@ -120,7 +345,7 @@ mod tests {
 
     struct TestObject {
         name: Option<String>,
-        handle: usize,
+        handle: Handle<TestObject>,
         value: i32,
     }
 
@@ -128,10 +353,13 @@ mod tests {
         fn get_name(&self) -> Option<String> {
             self.name.clone()
         }
-        fn set_handle(&mut self, handle: usize) {
+        fn set_name(&mut self, name: Option<String>) {
+            self.name = name;
+        }
+        fn set_handle(&mut self, handle: Handle<Self>) {
             self.handle = handle;
         }
-        fn get_handle(&self) -> usize {
+        fn get_handle(&self) -> Handle<Self> {
             self.handle
         }
     }
@@ -140,7 +368,7 @@ mod tests {
         fn new(name: Option<&str>, value: i32) -> Self {
             Self {
                 name: name.map(|s| s.to_string()),
-                handle: 0,
+                handle: Handle::invalid(),
                 value,
             }
         }
@@ -149,10 +377,10 @@ mod tests {
     #[test]
     fn test_iteration() {
         let mut db = ObjDB::new();
-        let h1 = db.add(TestObject::new(Some("a"), 1));
-        let h2 = db.add(TestObject::new(Some("b"), 2));
+        let h1 = db.add(TestObject::new(Some("a"), 1)).unwrap();
+        db.add(TestObject::new(Some("b"), 2)).unwrap();
         db.remove(h1);
-        let h3 = db.add(TestObject::new(Some("c"), 3));
+        db.add(TestObject::new(Some("c"), 3)).unwrap();
 
         // Iterate by reference
         let mut count = 0;
@@ -180,4 +408,150 @@ mod tests {
         }
         assert_eq!(sum, 4 + 6);
     }
+
+    #[test]
+    fn test_stale_handles() {
+        let mut db = ObjDB::new();
+        let h1 = db.add(TestObject::new(None, 1)).unwrap();
+        assert_eq!(db.remove(h1).map(|o| o.value), Some(1));
+
+        // The slot is reused, but the old handle does not resolve to the new object.
+        let h2 = db.add(TestObject::new(None, 2)).unwrap();
+        assert_eq!(h1.get_index(), h2.get_index());
+        assert_ne!(h1, h2);
+        assert!(db.get(h1).is_none());
+        assert!(db.get_mut(h1).is_none());
+        assert!(db.remove(h1).is_none());
+        assert_eq!(db.get(h2).map(|o| o.value), Some(2));
+        assert_eq!(db.get(h2).unwrap().get_handle(), h2);
+    }
+
+    #[test]
+    fn test_foreign_and_invalid_handles() {
+        let mut db1 = ObjDB::new();
+        let mut db2 = ObjDB::new();
+        let h1 = db1.add(TestObject::new(None, 1)).unwrap();
+        db2.add(TestObject::new(None, 2)).unwrap();
+
+        assert!(db2.get(h1).is_none());
+        assert!(db2.remove(h1).is_none());
+        assert!(db1.get(Handle::invalid()).is_none());
+        assert!(db1.contains(h1));
+    }
+
+    #[test]
+    fn test_name_policy_reject() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::Reject);
+        let h1 = db.add(TestObject::new(Some("Room"), 1)).unwrap();
+
+        assert_eq!(
+            db.add(TestObject::new(Some("Room"), 2)).err(),
+            Some(ObjDBError::DuplicateName("Room".to_string()))
+        );
+        assert_eq!(db.get(h1).map(|o| o.value), Some(1));
+        assert_eq!(db.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_name_policy_replace() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::Replace);
+        let h1 = db.add(TestObject::new(Some("Room"), 1)).unwrap();
+        let h2 = db.add(TestObject::new(Some("Room"), 2)).unwrap();
+
+        // The old handle does not alias the new object, which gets the name.
+        assert_ne!(h1, h2);
+        assert!(db.get(h1).is_none());
+        assert_eq!(db.get(h2).map(|o| o.value), Some(2));
+        assert_eq!(db.handle_of("Room"), Some(h2));
+        assert_eq!(db.iter().count(), 1);
+        let replaced = db.take_replaced();
+        assert_eq!(replaced.iter().map(|o| o.value).collect::<Vec<_>>(), [1]);
+        assert!(db.take_replaced().is_empty());
+
+        // Renaming onto a taken name removes the previous holder.
+        let h3 = db.add(TestObject::new(Some("Chair"), 3)).unwrap();
+        db.rename(h3, Some("Room".to_string())).unwrap();
+        assert!(db.get(h2).is_none());
+        assert_eq!(db.take_replaced().len(), 1);
+        assert_eq!(db.handle_of("Room"), Some(h3));
+        assert_eq!(db.handle_of("Chair"), None);
+    }
+
+    #[test]
+    fn test_name_policy_auto_suffix() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::AutoSuffix);
+        let h1 = db.add(TestObject::new(Some("Room"), 1)).unwrap();
+        let h2 = db.add(TestObject::new(Some("Room"), 2)).unwrap();
+        let h3 = db.add(TestObject::new(Some("Room.001"), 3)).unwrap();
+
+        assert_eq!(db.get(h1).unwrap().get_name().as_deref(), Some("Room"));
+        assert_eq!(db.get(h2).unwrap().get_name().as_deref(), Some("Room.001"));
+        assert_eq!(db.get(h3).unwrap().get_name().as_deref(), Some("Room.002"));
+        assert_eq!(db.handle_of("Room.002"), Some(h3));
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::Reject);
+        let h1 = db.add(TestObject::new(Some("a"), 1)).unwrap();
+        let h2 = db.add(TestObject::new(Some("b"), 2)).unwrap();
+
+        db.rename(h1, Some("c".to_string())).unwrap();
+        assert_eq!(db.handle_of("a"), None);
+        assert_eq!(db.handle_of("c"), Some(h1));
+        assert_eq!(db.get(h1).unwrap().get_name().as_deref(), Some("c"));
+
+        assert_eq!(
+            db.rename(h2, Some("c".to_string())),
+            Err(ObjDBError::DuplicateName("c".to_string()))
+        );
+        assert_eq!(db.handle_of("b"), Some(h2));
+
+        db.rename(h2, None).unwrap();
+        assert_eq!(db.handle_of("b"), None);
+
+        db.remove(h1);
+        assert_eq!(db.rename(h1, None), Err(ObjDBError::InvalidHandle));
+    }
+
+    #[test]
+    fn test_names_with_slot_reuse() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::Reject);
+        let h1 = db.add(TestObject::new(Some("a"), 1)).unwrap();
+        db.remove(h1);
+
+        // The freed name and slot can both be reused.
+        let h2 = db.add(TestObject::new(Some("a"), 2)).unwrap();
+        assert_eq!(h1.get_index(), h2.get_index());
+        assert_eq!(db.handle_of("a"), Some(h2));
+        assert_eq!(db.get_by_name("a").map(|o| o.value), Some(2));
+
+        // A slot reused by an unnamed object does not keep the old name.
+        db.remove(h2);
+        let h3 = db.add(TestObject::new(None, 3)).unwrap();
+        assert_eq!(h2.get_index(), h3.get_index());
+        assert!(db.get_by_name("a").is_none());
+        assert_eq!(db.handle_of("a"), None);
+    }
+
+    #[test]
+    fn test_names_follow_get_mut() {
+        let mut db = ObjDB::with_name_policy(NamePolicy::Reject);
+        let h1 = db.add(TestObject::new(Some("a"), 1)).unwrap();
+        let h2 = db.add(TestObject::new(Some("b"), 2)).unwrap();
+
+        db.get_mut(h1).unwrap().set_name(Some("c".to_string()));
+        assert_eq!(db.handle_of("c"), Some(h1));
+        assert_eq!(db.handle_of("a"), None);
+
+        // The freed name is available again after the change was picked up.
+        let h3 = db.add(TestObject::new(Some("a"), 3)).unwrap();
+        assert_eq!(db.handle_of("a"), Some(h3));
+
+        // A conflicting change is reverted under the reject policy.
+        db.get_mut(h2).unwrap().set_name(Some("c".to_string()));
+        db.sync_names();
+        assert_eq!(db.get(h2).unwrap().get_name().as_deref(), Some("b"));
+        assert_eq!(db.handle_of("c"), Some(h1));
+    }
 }
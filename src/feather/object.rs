@@ -1,5 +1,13 @@
-pub trait Object {
+use super::handle::Handle;
+
+pub trait Object: Sized {
     fn get_name(&self) -> Option<String>;
-    fn set_handle(&mut self, handle: usize);
-    fn get_handle(&self) -> usize;
-}
\ No newline at end of file
+    /// Returns `true` if the object is called `name`. Worth overriding to avoid the copy made
+    /// by `get_name`, as name lookups call it often.
+    fn has_name(&self, name: &str) -> bool {
+        self.get_name().as_deref() == Some(name)
+    }
+    fn set_name(&mut self, name: Option<String>);
+    fn set_handle(&mut self, handle: Handle<Self>);
+    fn get_handle(&self) -> Handle<Self>;
+}
@@ -0,0 +1,261 @@
+use cgmath::{Angle, Deg, InnerSpace};
+
+use super::camera::Camera;
+use super::inputevent::{Key, MouseButton};
+use super::inputstate::InputState;
+use super::math::{Point3, Vec3};
+
+/// Orbits a camera around a target point, with Z as the up axis.
+///
+/// Dragging with the left mouse button rotates, dragging with the right or middle button pans
+/// and the wheel zooms. The arrow keys rotate as well.
+pub struct OrbitController {
+    target: Point3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    min_distance: f32,
+    max_distance: f32,
+    min_pitch: f32,
+    max_pitch: f32,
+    pan_bounds: Option<(Point3, Point3)>,
+    rotate_speed: f32,
+    key_rotate_speed: f32,
+    pan_speed: f32,
+    zoom_speed: f32,
+}
+
+impl OrbitController {
+    /// Creates a controller looking at the origin from (2, 2, 2), like `PerspectiveCamera::new`.
+    pub fn new() -> Self {
+        let mut controller = Self {
+            target: Point3::new(0.0, 0.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 1.0,
+            min_distance: 0.1,
+            max_distance: 100.0,
+            min_pitch: -89.0,
+            max_pitch: 89.0,
+            pan_bounds: None,
+            rotate_speed: 0.3,
+            key_rotate_speed: 90.0,
+            pan_speed: 0.002,
+            zoom_speed: 0.1,
+        };
+        controller.look_from(Point3::new(2.0, 2.0, 2.0));
+        controller
+    }
+
+    pub fn get_target(&self) -> Point3 {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: Point3) -> &mut Self {
+        self.target = self.clamp_target(target);
+        self
+    }
+
+    /// Returns the angle around the Z axis in degrees, 0 meaning the camera is on the +X side
+    /// of the target.
+    pub fn get_yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// Returns the elevation above the XY plane in degrees.
+    pub fn get_pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    pub fn set_yaw_pitch(&mut self, yaw: f32, pitch: f32) -> &mut Self {
+        self.yaw = yaw.rem_euclid(360.0);
+        self.pitch = pitch.clamp(self.min_pitch, self.max_pitch);
+        self
+    }
+
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn set_distance(&mut self, distance: f32) -> &mut Self {
+        self.distance = distance.clamp(self.min_distance, self.max_distance);
+        self
+    }
+
+    /// Moves the camera to look at the target from the given position.
+    pub fn look_from(&mut self, eye_position: Point3) -> &mut Self {
+        let offset = eye_position - self.target;
+        let distance = offset.magnitude();
+        if distance > 0.0 {
+            let yaw = offset.y.atan2(offset.x).to_degrees();
+            let pitch = (offset.z / distance).asin().to_degrees();
+            self.set_yaw_pitch(yaw, pitch).set_distance(distance);
+        }
+        self
+    }
+
+    /// Limits how close and how far the camera can zoom.
+    pub fn set_distance_limits(&mut self, min_distance: f32, max_distance: f32) -> &mut Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self.set_distance(self.distance)
+    }
+
+    /// Limits the elevation in degrees; keep it within (-90, 90) so the view never flips.
+    pub fn set_pitch_limits(&mut self, min_pitch: f32, max_pitch: f32) -> &mut Self {
+        self.min_pitch = min_pitch;
+        self.max_pitch = max_pitch;
+        self.set_yaw_pitch(self.yaw, self.pitch)
+    }
+
+    /// Keeps the target inside the box between two corners while panning, or anywhere for
+    /// `None`.
+    pub fn set_pan_bounds(&mut self, pan_bounds: Option<(Point3, Point3)>) -> &mut Self {
+        self.pan_bounds = pan_bounds;
+        self.set_target(self.target)
+    }
+
+    /// Sets the degrees rotated per dragged pixel and per second of a held arrow key.
+    pub fn set_rotate_speed(&mut self, rotate_speed: f32, key_rotate_speed: f32) -> &mut Self {
+        self.rotate_speed = rotate_speed;
+        self.key_rotate_speed = key_rotate_speed;
+        self
+    }
+
+    /// Sets the distance panned per dragged pixel, relative to the distance to the target.
+    pub fn set_pan_speed(&mut self, pan_speed: f32) -> &mut Self {
+        self.pan_speed = pan_speed;
+        self
+    }
+
+    /// Sets the fraction of the distance zoomed per scrolled line.
+    pub fn set_zoom_speed(&mut self, zoom_speed: f32) -> &mut Self {
+        self.zoom_speed = zoom_speed;
+        self
+    }
+
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) -> &mut Self {
+        self.set_yaw_pitch(self.yaw + yaw, self.pitch + pitch)
+    }
+
+    /// Moves the target in the view plane by the given pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32) -> &mut Self {
+        let forward = (self.target - self.get_eye_position()).normalize();
+        let right = forward.cross(Vec3::unit_z()).normalize();
+        let up = right.cross(forward);
+        let scale = self.pan_speed * self.distance;
+        self.set_target(self.target + (up * dy - right * dx) * scale)
+    }
+
+    /// Zooms in for positive and out for negative lines.
+    pub fn zoom(&mut self, lines: f32) -> &mut Self {
+        self.set_distance(self.distance * (1.0 - self.zoom_speed).powf(lines))
+    }
+
+    pub fn get_eye_position(&self) -> Point3 {
+        let (yaw, pitch) = (Deg(self.yaw), Deg(self.pitch));
+        let direction = Vec3::new(
+            pitch.cos() * yaw.cos(),
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+        );
+        self.target + direction * self.distance
+    }
+
+    /// Rotates, pans and zooms from the input of the current frame.
+    pub fn update(&mut self, input: &InputState, delta: f32) -> &mut Self {
+        let mouse = input.get_mouse_delta();
+        if input.is_button_down(MouseButton::Left) {
+            self.rotate(-mouse.x * self.rotate_speed, mouse.y * self.rotate_speed);
+        } else if input.is_button_down(MouseButton::Right)
+            || input.is_button_down(MouseButton::Middle)
+        {
+            self.pan(mouse.x, mouse.y);
+        }
+
+        let axis = |negative: Key, positive: Key| {
+            input.is_key_down(positive) as i32 as f32 - input.is_key_down(negative) as i32 as f32
+        };
+        let step = self.key_rotate_speed * delta;
+        self.rotate(
+            axis(Key::Right, Key::Left) * step,
+            axis(Key::Down, Key::Up) * step,
+        );
+
+        self.zoom(input.get_wheel_delta().y)
+    }
+
+    /// Points the camera at the target from the current orbit position.
+    pub fn apply(&self, camera: &mut dyn Camera) {
+        camera.set_view(self.get_eye_position(), self.target, Vec3::unit_z());
+    }
+
+    fn clamp_target(&self, target: Point3) -> Point3 {
+        match self.pan_bounds {
+            Some((min, max)) => Point3::new(
+                target.x.clamp(min.x, max.x),
+                target.y.clamp(min.y, max.y),
+                target.z.clamp(min.z, max.z),
+            ),
+            None => target,
+        }
+    }
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::inputevent::InputEvent;
+
+    fn assert_near(a: Point3, b: Point3) {
+        assert!((a - b).magnitude() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_orbit() {
+        let mut orbit = OrbitController::new();
+        assert_near(orbit.get_eye_position(), Point3::new(2.0, 2.0, 2.0));
+        assert!((orbit.get_yaw() - 45.0).abs() < 1e-4);
+
+        orbit.set_yaw_pitch(90.0, 0.0).set_distance(3.0);
+        assert_near(orbit.get_eye_position(), Point3::new(0.0, 3.0, 0.0));
+
+        // Limits clamp zooming, pitching and panning.
+        orbit.set_distance_limits(1.0, 5.0).zoom(-100.0);
+        assert_eq!(orbit.get_distance(), 5.0);
+        orbit.rotate(0.0, 180.0);
+        assert_eq!(orbit.get_pitch(), 89.0);
+        orbit.set_pan_bounds(Some((
+            Point3::new(-1.0, -1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+        )));
+        orbit.set_target(Point3::new(3.0, -3.0, 3.0));
+        assert_near(orbit.get_target(), Point3::new(1.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut orbit = OrbitController::new();
+        orbit.set_yaw_pitch(0.0, 0.0).set_rotate_speed(1.0, 90.0);
+
+        let mut input = InputState::new();
+        input.handle_event(&InputEvent::MouseMoved { x: 0.0, y: 0.0 });
+        input.handle_event(&InputEvent::MouseButtonPressed(MouseButton::Left));
+        input.handle_event(&InputEvent::MouseMoved { x: -30.0, y: 10.0 });
+        orbit.update(&input, 0.0);
+        assert!((orbit.get_yaw() - 30.0).abs() < 1e-4);
+        assert!((orbit.get_pitch() - 10.0).abs() < 1e-4);
+
+        input.end_frame();
+        input.handle_event(&InputEvent::MouseWheel { x: 0.0, y: 1.0 });
+        let distance = orbit.get_distance();
+        orbit.update(&input, 0.0);
+        assert!((orbit.get_distance() - distance * 0.9).abs() < 1e-4);
+    }
+}
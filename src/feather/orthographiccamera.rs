@@ -0,0 +1,260 @@
+use std::cell::Cell;
+
+use super::camera::Camera;
+use super::math::{Mat4, Point3, Vec3};
+
+/// How the visible area of an `OrthographicCamera` is chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Extent {
+    /// Fixed view space bounds, stretched to the screen.
+    Bounds {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+    },
+    /// A centered area of the given height; the width follows the aspect ratio of the screen.
+    FitHeight(f32),
+}
+
+pub struct OrthographicCamera {
+    projection: Cell<Option<Mat4>>,
+    view: Cell<Option<Mat4>>,
+    extent: Extent,
+    near: f32,
+    far: f32,
+    screen_width: u32,
+    screen_height: u32,
+    eye_position: Point3,
+    target_position: Point3,
+    up_vector: Vec3,
+}
+
+impl Camera for OrthographicCamera {
+    fn get_projection(&self) -> Mat4 {
+        match self.projection.get() {
+            Some(projection) => projection,
+            None => {
+                #[rustfmt::skip]
+                let correction = Mat4::new(
+                    1.0,  0.0,       0.0, 0.0,
+                    0.0, -1.0,       0.0, 0.0,
+                    0.0,  0.0, 1.0 / 2.0, 0.0,
+                    0.0,  0.0, 1.0 / 2.0, 1.0,
+                );
+
+                let (left, right, bottom, top) = self.get_bounds();
+                let projection =
+                    correction * cgmath::ortho(left, right, bottom, top, self.near, self.far);
+                self.projection.set(Some(projection));
+                projection
+            }
+        }
+    }
+
+    fn get_view(&self) -> Mat4 {
+        match self.view.get() {
+            Some(view) => view,
+            None => {
+                let view =
+                    Mat4::look_at_rh(self.eye_position, self.target_position, self.up_vector);
+                self.view.set(Some(view));
+                view
+            }
+        }
+    }
+
+    fn set_view(&mut self, eye_position: Point3, target_position: Point3, up_vector: Vec3) {
+        OrthographicCamera::set_view(self, eye_position, target_position, up_vector);
+    }
+
+    fn set_viewport(&mut self, width: u32, height: u32) {
+        self.set_screen_dimention(width, height);
+    }
+}
+
+impl OrthographicCamera {
+    /// Creates a camera looking at the origin from (2, 2, 2), with +Z up like the rest of the
+    /// engine.
+    pub fn new() -> Self {
+        Self {
+            projection: Cell::new(None),
+            view: Cell::new(None),
+            extent: Extent::FitHeight(2.0),
+            near: 0.1,
+            far: 10.0,
+            screen_width: 1920,
+            screen_height: 1080,
+            eye_position: Point3::new(2.0, 2.0, 2.0),
+            target_position: Point3::new(0.0, 0.0, 0.0),
+            up_vector: Vec3::unit_z(),
+        }
+    }
+
+    /// Returns the visible view space area as (left, right, bottom, top).
+    pub fn get_bounds(&self) -> (f32, f32, f32, f32) {
+        match self.extent {
+            Extent::Bounds {
+                left,
+                right,
+                bottom,
+                top,
+            } => (left, right, bottom, top),
+            Extent::FitHeight(height) => {
+                let aspect = self.screen_width as f32 / self.screen_height as f32;
+                let (half_width, half_height) = (height * aspect / 2.0, height / 2.0);
+                (-half_width, half_width, -half_height, half_height)
+            }
+        }
+    }
+
+    /// Shows exactly the given view space area, whatever the aspect ratio of the screen.
+    pub fn set_bounds(&mut self, left: f32, right: f32, bottom: f32, top: f32) -> &mut Self {
+        self.set_extent(Extent::Bounds {
+            left,
+            right,
+            bottom,
+            top,
+        })
+    }
+
+    /// Shows a centered area of the given height, with the width derived from the aspect ratio.
+    pub fn set_fit_height(&mut self, height: f32) -> &mut Self {
+        self.set_extent(Extent::FitHeight(height))
+    }
+
+    fn set_extent(&mut self, extent: Extent) -> &mut Self {
+        if extent != self.extent {
+            self.extent = extent;
+            self.projection.set(None);
+        }
+        self
+    }
+
+    pub fn set_screen_dimention(&mut self, width: u32, height: u32) -> &mut Self {
+        if width != self.screen_width || height != self.screen_height {
+            self.screen_width = width;
+            self.screen_height = height;
+            self.projection.set(None);
+        }
+        self
+    }
+
+    pub fn set_near_far(&mut self, near: f32, far: f32) -> &mut Self {
+        if near != self.near || far != self.far {
+            self.near = near;
+            self.far = far;
+            self.projection.set(None);
+        }
+        self
+    }
+
+    pub fn set_near(&mut self, near: f32) -> &mut Self {
+        if near != self.near {
+            self.near = near;
+            self.projection.set(None);
+        }
+        self
+    }
+
+    pub fn set_far(&mut self, far: f32) -> &mut Self {
+        if far != self.far {
+            self.far = far;
+            self.projection.set(None);
+        }
+        self
+    }
+
+    pub fn set_view(
+        &mut self,
+        eye_position: Point3,
+        target_position: Point3,
+        up_vector: Vec3,
+    ) -> &mut Self {
+        if eye_position != self.eye_position
+            || target_position != self.target_position
+            || up_vector != self.up_vector
+        {
+            self.eye_position = eye_position;
+            self.target_position = target_position;
+            self.up_vector = up_vector;
+            self.view.set(None);
+        }
+        self
+    }
+
+    pub fn set_eye_position(&mut self, eye_position: Point3) -> &mut Self {
+        if eye_position != self.eye_position {
+            self.eye_position = eye_position;
+            self.view.set(None);
+        }
+        self
+    }
+
+    pub fn set_target_position(&mut self, target_position: Point3) -> &mut Self {
+        if target_position != self.target_position {
+            self.target_position = target_position;
+            self.view.set(None);
+        }
+        self
+    }
+
+    pub fn set_up_vector(&mut self, up_vector: Vec3) -> &mut Self {
+        if up_vector != self.up_vector {
+            self.up_vector = up_vector;
+            self.view.set(None);
+        }
+        self
+    }
+}
+
+impl Default for OrthographicCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::math::Vec4;
+
+    #[test]
+    fn test_projection() {
+        let mut camera = OrthographicCamera::new();
+        camera
+            .set_bounds(-4.0, 4.0, -1.0, 3.0)
+            .set_near_far(1.0, 5.0);
+
+        // Vulkan clip space: y points down and depth goes from 0 to 1.
+        let projection = camera.get_projection();
+        let clip = projection * Vec4::new(-4.0, 3.0, -1.0, 1.0);
+        assert_eq!(clip, Vec4::new(-1.0, -1.0, 0.0, 1.0));
+        let clip = projection * Vec4::new(4.0, -1.0, -5.0, 1.0);
+        assert_eq!(clip, Vec4::new(1.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_fit_height() {
+        let mut camera = OrthographicCamera::new();
+        camera.set_fit_height(4.0).set_screen_dimention(800, 400);
+        assert_eq!(camera.get_bounds(), (-4.0, 4.0, -2.0, 2.0));
+
+        // The cached projection follows the screen size.
+        let projection = camera.get_projection();
+        camera.set_screen_dimention(400, 400);
+        assert_ne!(camera.get_projection(), projection);
+        assert_eq!(camera.get_bounds(), (-2.0, 2.0, -2.0, 2.0));
+    }
+
+    #[test]
+    fn test_default_up() {
+        // Points above the target along +Z end up above it on the screen.
+        let camera = OrthographicCamera::new();
+        let view = camera.get_view();
+        let below = view * Vec4::new(0.0, 0.0, 0.0, 1.0);
+        let above = view * Vec4::new(0.0, 0.0, 1.0, 1.0);
+        assert!(above.y > below.y);
+        assert!((above.x - below.x).abs() < 1e-6);
+    }
+}
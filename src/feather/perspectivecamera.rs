@@ -54,6 +54,14 @@ impl Camera for PerspectiveCamera {
             }
         }
     }
+
+    fn set_view(&mut self, eye_position: Point3, target_position: Point3, up_vector: Vec3) {
+        PerspectiveCamera::set_view(self, eye_position, target_position, up_vector);
+    }
+
+    fn set_viewport(&mut self, width: u32, height: u32) {
+        self.set_screen_dimention(width, height);
+    }
 }
 
 impl PerspectiveCamera {
@@ -46,11 +46,14 @@ unsafe fn check_physical_device(
     physical_device: vk::PhysicalDevice,
 ) -> Result<()> {
     QueueFamilyIndices::get(instance, data.surface, physical_device)?;
-    check_physical_device_extensions(instance, physical_device)?;
 
-    let support = SwapchainSupport::get(instance, data.surface, physical_device)?;
-    if support.formats.is_empty() || support.present_modes.is_empty() {
-        return Err(anyhow!(SuitabilityError("Insufficient swapchain support.")));
+    if !data.headless {
+        check_physical_device_extensions(instance, physical_device)?;
+
+        let support = SwapchainSupport::get(instance, data.surface, physical_device)?;
+        if support.formats.is_empty() || support.present_modes.is_empty() {
+            return Err(anyhow!(SuitabilityError("Insufficient swapchain support.")));
+        }
     }
 
     let features = instance.get_physical_device_features(physical_device);
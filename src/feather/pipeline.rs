@@ -1,10 +1,22 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use anyhow::{anyhow, Result};
 use vulkanalia::bytecode::Bytecode;
 use vulkanalia::prelude::v1_0::*;
 
 use super::appdata::AppData;
 use super::dephobjects::get_depth_format;
-use super::vertex::Vertex;
+use super::pushconstants::PushConstants;
+use super::shader::Shader;
+use super::vertexlayout::VertexLayout;
+
+/// The pipelines compiled for one `Shader` and vertex layout.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ShaderPipelines {
+    pub opaque: vk::Pipeline,
+    pub transparent: vk::Pipeline,
+}
 
 //================================================
 // Pipeline
@@ -37,6 +49,12 @@ pub unsafe fn create_render_pass(
         .initial_layout(vk::ImageLayout::UNDEFINED)
         .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
 
+    // Offscreen images are only ever read back, so they end up ready for a copy.
+    let resolve_final_layout = match data.swapchain.is_offscreen() {
+        true => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        false => vk::ImageLayout::PRESENT_SRC_KHR,
+    };
+
     let color_resolve_attachment = vk::AttachmentDescription::builder()
         .format(data.swapchain.swapchain_format)
         .samples(vk::SampleCountFlags::_1)
@@ -45,7 +63,7 @@ pub unsafe fn create_render_pass(
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+        .final_layout(resolve_final_layout);
 
     // Subpasses
 
@@ -108,34 +126,96 @@ pub unsafe fn create_render_pass(
 }
 
 pub unsafe fn create_descriptor_set_layout(device: &Device, data: &mut AppData) -> Result<()> {
+    // Set 0: per camera data.
+
     let ubo_binding = vk::DescriptorSetLayoutBinding::builder()
         .binding(0)
-        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
         .descriptor_count(1)
         .stage_flags(vk::ShaderStageFlags::VERTEX);
 
+    let bindings = &[ubo_binding];
+    let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(bindings);
+
+    data.descriptor_set_layout = device.create_descriptor_set_layout(&info, None)?;
+
+    // Set 1: per material data.
+
     let sampler_binding = vk::DescriptorSetLayoutBinding::builder()
-        .binding(1)
+        .binding(0)
         .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
         .descriptor_count(1)
         .stage_flags(vk::ShaderStageFlags::FRAGMENT);
 
-    let bindings = &[ubo_binding, sampler_binding];
+    let bindings = &[sampler_binding];
     let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(bindings);
 
-    data.descriptor_set_layout = device.create_descriptor_set_layout(&info, None)?;
+    data.material_descriptor_set_layout = device.create_descriptor_set_layout(&info, None)?;
 
     Ok(())
 }
 
+/// Creates the pipeline layout shared by all shaders and the pipelines of the default shader
+/// for the default vertex layout.
 pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()> {
-    // Stages
+    // Push Constant Ranges
+
+    let push_constant_range = vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+        .offset(0)
+        .size(size_of::<PushConstants>() as u32);
 
-    let vert = include_bytes!("../../shaders/26/vert.spv");
-    let frag = include_bytes!("../../shaders/26/frag.spv");
+    // Layout
 
-    let vert_shader_module = create_shader_module(device, &vert[..])?;
-    let frag_shader_module = create_shader_module(device, &frag[..])?;
+    let set_layouts = &[
+        data.descriptor_set_layout,
+        data.material_descriptor_set_layout,
+    ];
+    let push_constant_ranges = &[push_constant_range];
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(push_constant_ranges);
+
+    data.pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+
+    create_shader_pipelines(device, data, &Shader::default(), &VertexLayout::default())
+}
+
+/// Returns true if the pipelines of a shader for meshes with the given layout exist.
+pub fn has_shader_pipelines(
+    pipelines: &HashMap<String, HashMap<VertexLayout, ShaderPipelines>>,
+    shader: &Shader,
+    layout: &VertexLayout,
+) -> bool {
+    pipelines
+        .get(shader.get_name())
+        .is_some_and(|p| p.contains_key(layout))
+}
+
+/// Creates the opaque and transparent pipelines of a shader, reading vertices of the given
+/// layout, unless they already exist. Fails if the layout lacks attributes the shader reads.
+pub unsafe fn create_shader_pipelines(
+    device: &Device,
+    data: &mut AppData,
+    shader: &Shader,
+    layout: &VertexLayout,
+) -> Result<()> {
+    if has_shader_pipelines(&data.pipelines, shader, layout) {
+        return Ok(());
+    }
+    let missing = shader.missing_attributes(layout);
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "Shader {} reads vertex attributes {:?} missing from the layout",
+            shader.get_name(),
+            missing
+        ));
+    }
+
+    // Stages
+
+    let vert_shader_module = create_shader_module(device, shader.get_vertex_code())?;
+    let frag_shader_module = create_shader_module(device, shader.get_fragment_code())?;
 
     let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
@@ -149,8 +229,8 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
 
     // Vertex Input State
 
-    let binding_descriptions = &[Vertex::binding_description()];
-    let attribute_descriptions = Vertex::attribute_descriptions();
+    let binding_descriptions = &[layout.binding_description()];
+    let attribute_descriptions = layout.attribute_descriptions();
     let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
         .vertex_binding_descriptions(binding_descriptions)
         .vertex_attribute_descriptions(&attribute_descriptions);
@@ -181,6 +261,11 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .viewports(viewports)
         .scissors(scissors);
 
+    // Every scene sets the viewport and scissor of its own part of the screen.
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state =
+        vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(dynamic_states);
+
     // Rasterization State
 
     let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
@@ -208,6 +293,14 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .depth_bounds_test_enable(false)
         .stencil_test_enable(false);
 
+    // Transparent objects are depth tested against the opaque ones but do not occlude each other.
+    let transparent_depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(false)
+        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_bounds_test_enable(false)
+        .stencil_test_enable(false);
+
     // Color Blend State
 
     let attachment = vk::PipelineColorBlendAttachmentState::builder()
@@ -221,12 +314,22 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .attachments(attachments)
         .blend_constants([0.0, 0.0, 0.0, 0.0]);
 
-    // Layout
-
-    let set_layouts = &[data.descriptor_set_layout];
-    let layout_info = vk::PipelineLayoutCreateInfo::builder().set_layouts(set_layouts);
-
-    data.pipeline_layout = device.create_pipeline_layout(&layout_info, None)?;
+    let transparent_attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(true)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .alpha_blend_op(vk::BlendOp::ADD);
+
+    let transparent_attachments = &[transparent_attachment];
+    let transparent_color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(transparent_attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
 
     // Create
 
@@ -240,13 +343,38 @@ pub unsafe fn create_pipeline(device: &Device, data: &mut AppData) -> Result<()>
         .multisample_state(&multisample_state)
         .depth_stencil_state(&depth_stencil_state)
         .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
         .layout(data.pipeline_layout)
         .render_pass(data.render_pass)
         .subpass(0);
 
-    data.pipeline = device
-        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)?
-        .0[0];
+    let transparent_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&transparent_depth_stencil_state)
+        .color_blend_state(&transparent_color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(data.pipeline_layout)
+        .render_pass(data.render_pass)
+        .subpass(0);
+
+    let pipelines = device
+        .create_graphics_pipelines(vk::PipelineCache::null(), &[info, transparent_info], None)?
+        .0;
+    data.pipelines
+        .entry(shader.get_name().to_string())
+        .or_default()
+        .insert(
+            layout.clone(),
+            ShaderPipelines {
+                opaque: pipelines[0],
+                transparent: pipelines[1],
+            },
+        );
 
     // Cleanup
 
@@ -0,0 +1,9 @@
+use super::math::{Mat4, Vec4};
+
+/// Per draw data pushed to both shader stages.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct PushConstants {
+    pub model: Mat4,
+    pub base_color: Vec4,
+}
@@ -24,15 +24,20 @@ impl QueueFamilyIndices {
             .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
             .map(|i| i as u32);
 
+        // Headless rendering has no surface, the graphics queue is used instead.
         let mut present = None;
-        for (index, _properties) in properties.iter().enumerate() {
-            if instance.get_physical_device_surface_support_khr(
-                physical_device,
-                index as u32,
-                surface,
-            )? {
-                present = Some(index as u32);
-                break;
+        if surface.is_null() {
+            present = graphics;
+        } else {
+            for (index, _properties) in properties.iter().enumerate() {
+                if instance.get_physical_device_surface_support_khr(
+                    physical_device,
+                    index as u32,
+                    surface,
+                )? {
+                    present = Some(index as u32);
+                    break;
+                }
             }
         }
 
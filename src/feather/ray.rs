@@ -0,0 +1,119 @@
+use cgmath::InnerSpace;
+
+use super::handle::Handle;
+use super::math::{Point3, Vec3};
+use super::node::Node;
+
+/// A half line in world space, used for picking.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3,
+    /// Always of unit length, so distances along the ray are world space distances.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Returns the point at the given distance along the ray.
+    pub fn at(&self, distance: f32) -> Point3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distance at which the ray enters the box between two corners, 0 if it starts
+    /// inside, or `None` if it misses.
+    pub fn intersect_box(&self, min: Point3, max: Point3) -> Option<f32> {
+        let (mut near, mut far) = (0.0f32, f32::INFINITY);
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let t0 = (min[axis] - self.origin[axis]) * inverse;
+            let t1 = (max[axis] - self.origin[axis]) * inverse;
+            // A NaN from a ray lying in a slab plane leaves the interval unchanged.
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        (near <= far).then_some(near)
+    }
+
+    /// Intersects the triangle from both sides and returns the distance and the barycentric
+    /// weights of its three corners at the hit.
+    pub fn intersect_triangle(&self, a: Point3, b: Point3, c: Point3) -> Option<(f32, Vec3)> {
+        // Möller–Trumbore.
+        let (edge1, edge2) = (b - a, c - a);
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = s.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = edge2.dot(q) * inverse;
+        (distance >= 0.0).then_some((distance, Vec3::new(1.0 - u - v, u, v)))
+    }
+}
+
+/// The nearest intersection of a ray with the meshes of a scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub node: Handle<Node>,
+    /// The index of the triangle in the mesh, i.e. its first index divided by 3.
+    pub triangle: usize,
+    /// The weights of the triangle's three vertices at the hit point.
+    pub barycentrics: Vec3,
+    /// The world space distance from the ray origin.
+    pub distance: f32,
+    /// The hit point in world space.
+    pub point: Point3,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect_box() {
+        let ray = Ray::new(Point3::new(-5.0, 0.5, 0.5), Vec3::new(2.0, 0.0, 0.0));
+        let (min, max) = (Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        assert_eq!(ray.intersect_box(min, max), Some(5.0));
+        assert_eq!(ray.at(5.0), Point3::new(0.0, 0.5, 0.5));
+
+        // Starting inside hits immediately, the box behind the ray is missed.
+        let inside = Ray::new(Point3::new(0.5, 0.5, 0.5), Vec3::unit_y());
+        assert_eq!(inside.intersect_box(min, max), Some(0.0));
+        let behind = Ray::new(Point3::new(2.0, 0.5, 0.5), Vec3::unit_x());
+        assert_eq!(behind.intersect_box(min, max), None);
+    }
+
+    #[test]
+    fn test_intersect_triangle() {
+        let (a, b, c) = (
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(Point3::new(0.25, 0.5, 2.0), -Vec3::unit_z());
+        let (distance, barycentrics) = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((distance - 2.0).abs() < 1e-6);
+        assert!((barycentrics - Vec3::new(0.25, 0.25, 0.5)).magnitude() < 1e-6);
+
+        // Triangles are hit from the back as well, but not outside their edges.
+        let back = Ray::new(Point3::new(0.25, 0.5, -2.0), Vec3::unit_z());
+        assert!(back.intersect_triangle(a, b, c).is_some());
+        let outside = Ray::new(Point3::new(0.75, 0.5, 2.0), -Vec3::unit_z());
+        assert!(outside.intersect_triangle(a, b, c).is_none());
+    }
+}
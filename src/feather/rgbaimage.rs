@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use anyhow::{anyhow, Result};
+
+use super::textureloader::{self, TextureError};
+
+/// An 8-bit RGBA image in host memory, rows top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err(anyhow!("Image size does not match its pixel data."));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Creates an image from BGRA pixels, as used by most swapchain formats.
+    pub fn from_bgra(width: u32, height: u32, mut pixels: Vec<u8>) -> Result<Self> {
+        pixels.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        Self::new(width, height, pixels)
+    }
+
+    /// Loads a PNG file, converting it to RGBA like textures are.
+    pub fn load_png(path: &str) -> Result<Self, TextureError> {
+        let atlas = textureloader::load_png_file(path)?;
+        Ok(Self {
+            width: atlas.get_width(),
+            height: atlas.get_height(),
+            pixels: atlas.get_pixels().to_vec(),
+        })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    /// Writes the image to a PNG file.
+    pub fn save_png(&self, path: &str) -> Result<(), TextureError> {
+        let file = File::create(path).map_err(|e| TextureError::Io(path.to_string(), e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_pixel() {
+        let pixels = (0..2 * 3 * 4).map(|i| i as u8).collect();
+        let image = RgbaImage::new(2, 3, pixels).unwrap();
+        assert_eq!(image.get_pixel(0, 0), [0, 1, 2, 3]);
+        assert_eq!(image.get_pixel(1, 2), [20, 21, 22, 23]);
+        assert!(RgbaImage::new(2, 2, vec![0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_save_png() {
+        let image = RgbaImage::from_bgra(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(image.get_pixel(0, 0), [3, 2, 1, 4]);
+
+        let path = std::env::temp_dir().join("feather_test_save_png.png");
+        let path = path.to_str().unwrap();
+        image.save_png(path).unwrap();
+        let loaded = RgbaImage::load_png(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, image);
+    }
+}
@@ -0,0 +1,37 @@
+use vulkanalia::vk;
+
+/// Filtering and addressing used when a material samples its texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerSettings {
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub address_mode: vk::SamplerAddressMode,
+    /// Maximum anisotropy, or `None` to disable anisotropic filtering.
+    pub anisotropy: Option<f32>,
+    pub mipmaps: bool,
+}
+
+impl SamplerSettings {
+    /// Nearest filtering without mipmaps, for pixel art and lookup textures.
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: vk::Filter::NEAREST,
+            min_filter: vk::Filter::NEAREST,
+            anisotropy: None,
+            mipmaps: false,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            address_mode: vk::SamplerAddressMode::REPEAT,
+            anisotropy: Some(16.0),
+            mipmaps: true,
+        }
+    }
+}
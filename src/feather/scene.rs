@@ -1,108 +1,585 @@
-use anyhow::Result;
-use vulkanalia::Device;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use cgmath::SquareMatrix;
+use vulkanalia::vk::{CommandPool, DescriptorSetLayout, DeviceV1_0, PhysicalDevice, Queue};
+use vulkanalia::{Device, Instance};
 
 use crate::feather::object::Object;
 
-use super::{mesh::Mesh, meshbuffer::MeshBuffer, node::Node, objdb::ObjDB};
+use super::{
+    atlas::Atlas,
+    handle::Handle,
+    material::Material,
+    math::{Mat4, Point3},
+    mesh::Mesh,
+    meshbuffer::MeshBuffer,
+    node::{move_node_next_to, set_node_index, Node},
+    nodeiter::{BreadthFirstNodeIter, DepthFirstNodeIter},
+    objdb::{NamePolicy, ObjDB},
+    ray::{Ray, RaycastHit},
+    texture::Texture,
+    textureloader::{self, TextureError},
+    vertexlayout::VertexLayout,
+};
 
 pub struct Scene {
     pub meshes: ObjDB<Mesh>,
     pub buffers: ObjDB<MeshBuffer>,
     pub nodes: ObjDB<Node>,
+    pub atlases: ObjDB<Atlas>,
+    pub materials: ObjDB<Material>,
+    /// The root nodes in their drawing and traversal order.
+    root_nodes: Vec<Handle<Node>>,
     needs_create_mesh_buffer: bool,
 }
 
 impl Scene {
     pub fn new() -> Self {
         Self {
-            meshes: ObjDB::new(),
+            meshes: ObjDB::with_name_policy(NamePolicy::AutoSuffix),
             buffers: ObjDB::new(),
-            nodes: ObjDB::new(),
+            nodes: ObjDB::with_name_policy(NamePolicy::AutoSuffix),
+            atlases: ObjDB::with_name_policy(NamePolicy::AutoSuffix),
+            materials: ObjDB::with_name_policy(NamePolicy::AutoSuffix),
+            root_nodes: Vec::new(),
             needs_create_mesh_buffer: false,
         }
     }
 
-    pub fn add_mesh(&mut self, mesh: Mesh) -> usize {
+    pub fn add_mesh(&mut self, mesh: Mesh) -> Result<Handle<Mesh>> {
+        let mesh_handle = self.meshes.add(mesh)?;
         self.needs_create_mesh_buffer = true;
-        self.meshes.add(mesh)
+        Ok(mesh_handle)
+    }
+
+    pub fn add_atlas(&mut self, atlas: Atlas) -> Result<Handle<Atlas>> {
+        Ok(self.atlases.add(atlas)?)
+    }
+
+    /// Loads a PNG file into a new atlas and returns a texture covering all of it.
+    pub fn load_texture(&mut self, path: &str) -> Result<Texture, TextureError> {
+        let atlas = textureloader::load_png_file(path)?;
+        Ok(Texture::full_atlas(self.atlases.add(atlas)?))
+    }
+
+    /// Like `load_texture`, but decodes a PNG already in memory.
+    pub fn load_texture_from_bytes(
+        &mut self,
+        name: Option<String>,
+        bytes: &[u8],
+    ) -> Result<Texture, TextureError> {
+        let atlas = textureloader::load_png_bytes(name, bytes)?;
+        Ok(Texture::full_atlas(self.atlases.add(atlas)?))
+    }
+
+    pub fn add_material(&mut self, material: Material) -> Result<Handle<Material>> {
+        Ok(self.materials.add(material)?)
+    }
+
+    /// Removes an atlas and destroys its image. Materials using it bind the fallback atlas of
+    /// `prepare_materials` from then on.
+    pub unsafe fn remove_atlas(&mut self, device: &Device, handle: Handle<Atlas>) -> Result<()> {
+        if !self.atlases.contains(handle) {
+            return Err(anyhow!("Atlas not found"));
+        }
+        // The atlas may still be sampled by frames in flight.
+        device.device_wait_idle()?;
+        self.atlases.remove(handle).unwrap().cleanup(device);
+        for material in self.materials.iter_mut() {
+            if material.get_texture().map(|t| t.get_atlas()) == Some(handle) {
+                material.cleanup(device);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a material and destroys its sampler and descriptor set. Nodes using it are drawn
+    /// with the default material.
+    pub unsafe fn remove_material(
+        &mut self,
+        device: &Device,
+        handle: Handle<Material>,
+    ) -> Result<()> {
+        if !self.materials.contains(handle) {
+            return Err(anyhow!("Material not found"));
+        }
+        // The descriptor set may still be bound by frames in flight.
+        device.device_wait_idle()?;
+        self.materials.remove(handle).unwrap().cleanup(device);
+        Ok(())
     }
 
-    pub fn create_root_node(&mut self, name: Option<String>) -> usize {
+    pub fn create_root_node(&mut self, name: Option<String>) -> Result<Handle<Node>> {
         let node = Node::new_root(name);
-        self.nodes.add(node)
+        let node_handle = self.nodes.add(node)?;
+        self.root_nodes.push(node_handle);
+        Ok(node_handle)
     }
 
-    pub fn create_node(&mut self, name: Option<String>, parent_handle: usize) -> usize {
+    pub fn create_node(
+        &mut self,
+        name: Option<String>,
+        parent_handle: Handle<Node>,
+    ) -> Result<Handle<Node>> {
+        if !self.nodes.contains(parent_handle) {
+            return Err(anyhow!("Parent node not found"));
+        }
         let node = Node::new(name, parent_handle);
-        let node_handle = self.nodes.add(node);
+        let node_handle = self.nodes.add(node)?;
         let parent_node = self.nodes.get_mut(parent_handle).unwrap();
         parent_node.add_child(node_handle);
-        node_handle
+        Ok(node_handle)
     }
 
-    pub fn disconnect_node(&mut self, node_handle: usize) {
-        let node = self.nodes.get_mut(node_handle).unwrap();
-        if let Some(parent) = node.get_parent() {
-            let parent_node = self.nodes.get_mut(parent).unwrap();
+    /// Detaches the node from its parent, making it the last root node.
+    pub fn disconnect_node(&mut self, node_handle: Handle<Node>) -> Result<()> {
+        let node = self
+            .nodes
+            .get_mut(node_handle)
+            .ok_or(anyhow!("Node not found"))?;
+        let parent = node.get_parent();
+        node.set_parent(None);
+        node.invalidate_global_transform();
+        if let Some(parent_node) = parent.and_then(|p| self.nodes.get_mut(p)) {
             parent_node.remove_child(node_handle);
         }
+        if !self.root_nodes.contains(&node_handle) {
+            self.root_nodes.push(node_handle);
+        }
+        Ok(())
+    }
+
+    /// Moves the node, with its whole subtree, under a new parent.
+    pub fn reparent(&mut self, node_handle: Handle<Node>, new_parent: Handle<Node>) -> Result<()> {
+        if !self.nodes.contains(node_handle) {
+            return Err(anyhow!("Node not found"));
+        }
+        if !self.nodes.contains(new_parent) {
+            return Err(anyhow!("Parent node not found"));
+        }
+
+        let mut ancestor = Some(new_parent);
+        while let Some(handle) = ancestor {
+            if handle == node_handle {
+                return Err(anyhow!("Node cannot become a descendant of itself"));
+            }
+            ancestor = self.nodes.get(handle).and_then(|n| n.get_parent());
+        }
+
+        self.disconnect_node(node_handle)?;
+        self.root_nodes.retain(|h| *h != node_handle);
+        self.nodes
+            .get_mut(node_handle)
+            .unwrap()
+            .set_parent(Some(new_parent));
+        self.nodes
+            .get_mut(new_parent)
+            .unwrap()
+            .add_child(node_handle);
+        Ok(())
+    }
+
+    /// Moves the node in front of a sibling in the child order of their parent, or in the
+    /// order of the root nodes.
+    pub fn move_node_before(
+        &mut self,
+        node_handle: Handle<Node>,
+        sibling: Handle<Node>,
+    ) -> Result<()> {
+        move_node_next_to(self.get_siblings_mut(node_handle)?, node_handle, sibling, 0)
+    }
+
+    /// Moves the node behind a sibling in the child order of their parent, or in the order of
+    /// the root nodes.
+    pub fn move_node_after(
+        &mut self,
+        node_handle: Handle<Node>,
+        sibling: Handle<Node>,
+    ) -> Result<()> {
+        move_node_next_to(self.get_siblings_mut(node_handle)?, node_handle, sibling, 1)
+    }
+
+    /// Moves the node to the given position among its siblings, or among the root nodes.
+    pub fn set_node_index(&mut self, node_handle: Handle<Node>, index: usize) -> Result<()> {
+        set_node_index(self.get_siblings_mut(node_handle)?, node_handle, index)
+    }
+
+    /// Returns the ordered list the node is in: the childreen of its parent or the root nodes.
+    fn get_siblings_mut(&mut self, node_handle: Handle<Node>) -> Result<&mut Vec<Handle<Node>>> {
+        let parent = self
+            .get_node(node_handle)
+            .ok_or(anyhow!("Node not found"))?
+            .get_parent();
+        match parent {
+            Some(parent) => Ok(self
+                .nodes
+                .get_mut(parent)
+                .ok_or(anyhow!("Parent node not found"))?
+                .get_childreen_mut()),
+            None => Ok(&mut self.root_nodes),
+        }
+    }
+
+    /// Removes the node together with all of its descendants.
+    pub fn remove_node_recursive(&mut self, node_handle: Handle<Node>) -> Result<()> {
+        self.disconnect_node(node_handle)?;
+        let subtree = self
+            .iter_depth_first_from(node_handle)
+            .map(|(handle, _, _)| handle)
+            .collect::<Vec<_>>();
+        for handle in subtree {
+            self.nodes.remove(handle);
+        }
+        self.root_nodes.retain(|h| *h != node_handle);
+        Ok(())
+    }
+
+    /// Finds a node by the names along its path from a root, e.g. "Scene root/Room/Chair".
+    pub fn find_by_path(&self, path: &str) -> Option<Handle<Node>> {
+        let mut names = path.split('/');
+        let root_name = names.next()?;
+        let mut current = self
+            .get_root_nodes()
+            .into_iter()
+            .find(|h| self.node_has_name(*h, root_name))?;
+        for name in names {
+            current = self
+                .get_node(current)?
+                .get_childreen()
+                .iter()
+                .copied()
+                .find(|h| self.node_has_name(*h, name))?;
+        }
+        Some(current)
     }
 
-    pub fn get_node(&self, handle: usize) -> Option<&Node> {
+    /// Returns `true` if the node and all of its ancestors are visible.
+    pub fn is_node_visible(&self, node_handle: Handle<Node>) -> bool {
+        let mut current = Some(node_handle);
+        while let Some(handle) = current {
+            match self.get_node(handle) {
+                Some(node) if node.is_visible() => current = node.get_parent(),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn node_has_name(&self, handle: Handle<Node>, name: &str) -> bool {
+        self.get_node(handle)
+            .is_some_and(|n| n.get_name().as_deref() == Some(name))
+    }
+
+    /// Iterates all nodes depth-first, starting at the root nodes.
+    pub fn iter_depth_first(&self) -> DepthFirstNodeIter<'_> {
+        let start = self
+            .get_root_nodes()
+            .into_iter()
+            .map(|h| (h, Mat4::identity()))
+            .collect();
+        DepthFirstNodeIter::new(self, start)
+    }
+
+    /// Iterates the subtree of the given node depth-first.
+    pub fn iter_depth_first_from(&self, node_handle: Handle<Node>) -> DepthFirstNodeIter<'_> {
+        let start = vec![(node_handle, self.get_parent_world_transform(node_handle))];
+        DepthFirstNodeIter::new(self, start)
+    }
+
+    /// Iterates all nodes breadth-first, starting at the root nodes.
+    pub fn iter_breadth_first(&self) -> BreadthFirstNodeIter<'_> {
+        let start = self
+            .get_root_nodes()
+            .into_iter()
+            .map(|h| (h, Mat4::identity()))
+            .collect();
+        BreadthFirstNodeIter::new(self, start)
+    }
+
+    /// Iterates the subtree of the given node breadth-first.
+    pub fn iter_breadth_first_from(&self, node_handle: Handle<Node>) -> BreadthFirstNodeIter<'_> {
+        let start = vec![(node_handle, self.get_parent_world_transform(node_handle))];
+        BreadthFirstNodeIter::new(self, start)
+    }
+
+    /// Computes the world transform of the node's parent from the local transforms, without
+    /// relying on the cached global transforms.
+    fn get_parent_world_transform(&self, node_handle: Handle<Node>) -> Mat4 {
+        let mut transform = Mat4::identity();
+        let mut current = self.get_node(node_handle).and_then(|n| n.get_parent());
+        while let Some(parent) = current.and_then(|h| self.get_node(h)) {
+            transform = parent.get_transform() * transform;
+            current = parent.get_parent();
+        }
+        transform
+    }
+
+    /// Returns the root nodes in their drawing and traversal order, which is the order they
+    /// were created or disconnected in unless they were moved.
+    pub fn get_root_nodes(&self) -> Vec<Handle<Node>> {
+        self.root_nodes.clone()
+    }
+
+    pub fn get_node(&self, handle: Handle<Node>) -> Option<&Node> {
         self.nodes.get(handle)
     }
 
-    pub fn get_node_mut(&mut self, handle: usize) -> Option<&mut Node> {
+    pub fn get_node_mut(&mut self, handle: Handle<Node>) -> Option<&mut Node> {
         self.nodes.get_mut(handle)
     }
 
-    pub fn get_mesh(&self, handle: usize) -> Option<&Mesh> {
+    /// Recomputes the world transform of every node whose local transform, or the local
+    /// transform of one of its ancestors, changed since the last update.
+    pub fn update_transforms(&mut self) {
+        for root in self.get_root_nodes() {
+            self.update_node_transform(root, Mat4::identity(), false);
+        }
+    }
+
+    fn update_node_transform(
+        &mut self,
+        node_handle: Handle<Node>,
+        parent_transform: Mat4,
+        parent_changed: bool,
+    ) {
+        let Some(node) = self.nodes.get_mut(node_handle) else {
+            return;
+        };
+
+        let (global_transform, changed) = match node.get_global_transform() {
+            Some(global_transform) if !parent_changed => (global_transform, false),
+            _ => {
+                let global_transform = parent_transform * node.get_transform();
+                node.set_global_transform(global_transform);
+                (global_transform, true)
+            }
+        };
+
+        let childreen = node.get_childreen().to_vec();
+        for child in childreen {
+            self.update_node_transform(child, global_transform, changed);
+        }
+    }
+
+    pub fn get_mesh(&self, handle: Handle<Mesh>) -> Option<&Mesh> {
         self.meshes.get(handle)
     }
 
-    pub fn node_set_mesh(&mut self, node_handle: usize, mesh_handle: usize) -> Result<()> {
+    /// Returns the nearest intersection of the ray with the meshes of the visible nodes. Each
+    /// node's world space bounding box is tested before its triangles.
+    pub fn raycast(&self, ray: &Ray) -> Option<RaycastHit> {
+        let mut nearest: Option<RaycastHit> = None;
+        for (node_handle, _, transform) in self.iter_depth_first().visible_only() {
+            let Some(mesh) = self
+                .get_node(node_handle)
+                .and_then(|n| n.get_mesh())
+                .and_then(|h| self.get_mesh(h))
+            else {
+                continue;
+            };
+            let Some((min, max)) = mesh.get_bounds() else {
+                continue;
+            };
+            let (world_min, world_max) = transform_bounds(transform, min, max);
+            match ray.intersect_box(world_min, world_max) {
+                Some(distance) if nearest.is_none_or(|hit| distance < hit.distance) => {}
+                _ => continue,
+            }
+
+            let to_world = |index: u32| {
+                let pos = mesh.vertices[index as usize].pos;
+                Point3::from_homogeneous(transform * pos.extend(1.0))
+            };
+            for (triangle, indices) in mesh.indices.chunks_exact(3).enumerate() {
+                let corners = (
+                    to_world(indices[0]),
+                    to_world(indices[1]),
+                    to_world(indices[2]),
+                );
+                let Some((distance, barycentrics)) =
+                    ray.intersect_triangle(corners.0, corners.1, corners.2)
+                else {
+                    continue;
+                };
+                if nearest.is_none_or(|hit| distance < hit.distance) {
+                    nearest = Some(RaycastHit {
+                        node: node_handle,
+                        triangle,
+                        barycentrics,
+                        distance,
+                        point: ray.at(distance),
+                    });
+                }
+            }
+        }
+        nearest
+    }
+
+    pub fn node_set_mesh(
+        &mut self,
+        node_handle: Handle<Node>,
+        mesh_handle: Handle<Mesh>,
+    ) -> Result<()> {
         self.get_node_mut(node_handle)
-            .ok_or(anyhow::anyhow!("Node not found"))?
+            .ok_or(anyhow!("Node not found"))?
             .set_mesh(mesh_handle);
         Ok(())
     }
 
-    pub fn create_mesh_buffer(&mut self) -> usize {
-        let mesh_buffer = MeshBuffer::new();
-        self.buffers.add(mesh_buffer)
+    pub fn get_atlas(&self, handle: Handle<Atlas>) -> Option<&Atlas> {
+        self.atlases.get(handle)
     }
 
+    pub fn get_material(&self, handle: Handle<Material>) -> Option<&Material> {
+        self.materials.get(handle)
+    }
+
+    pub fn get_material_mut(&mut self, handle: Handle<Material>) -> Option<&mut Material> {
+        self.materials.get_mut(handle)
+    }
+
+    pub fn node_set_material(
+        &mut self,
+        node_handle: Handle<Node>,
+        material_handle: Handle<Material>,
+    ) -> Result<()> {
+        if !self.materials.contains(material_handle) {
+            return Err(anyhow!("Material not found"));
+        }
+        self.get_node_mut(node_handle)
+            .ok_or(anyhow!("Node not found"))?
+            .set_material(material_handle);
+        Ok(())
+    }
+
+    pub fn create_mesh_buffer(&mut self, layout: VertexLayout) -> Result<Handle<MeshBuffer>> {
+        let mesh_buffer = MeshBuffer::new(layout);
+        Ok(self.buffers.add(mesh_buffer)?)
+    }
+
+    /// Packs the meshes which are not in a mesh buffer yet into new ones, one per vertex layout.
     pub fn build_missing_mesh_buffers(&mut self) -> Result<()> {
         if !self.needs_create_mesh_buffer {
             return Ok(());
         }
         let mut to_build = Vec::new();
+        // Empty meshes have nothing to draw and get no buffer.
         for mesh in self.meshes.iter() {
-            if !mesh.has_buffers_assigned() {
+            if !mesh.has_buffers_assigned() && !mesh.vertices.is_empty() && !mesh.indices.is_empty()
+            {
                 to_build.push(mesh.get_handle());
             }
         }
 
-        if to_build.is_empty() {
-            self.needs_create_mesh_buffer = false;
-            return Ok(());
-        }
-
-        let mesh_buffer_handle = self.create_mesh_buffer();
-        let mesh_buffer = self.buffers.get_mut(mesh_buffer_handle).unwrap();
-
+        let mut mesh_buffers = HashMap::new();
         for mesh_handle in to_build {
+            let layout = self.meshes.get(mesh_handle).unwrap().get_layout().clone();
+            let mesh_buffer_handle = match mesh_buffers.get(&layout) {
+                Some(handle) => *handle,
+                None => {
+                    let handle = self.create_mesh_buffer(layout.clone())?;
+                    mesh_buffers.insert(layout, handle);
+                    handle
+                }
+            };
+            let mesh_buffer = self.buffers.get_mut(mesh_buffer_handle).unwrap();
             let mesh = self.meshes.get_mut(mesh_handle).unwrap();
-            mesh_buffer.add_mesh(mesh);
+            mesh_buffer.add_mesh(mesh)?;
         }
         self.needs_create_mesh_buffer = false;
         Ok(())
     }
 
+    pub unsafe fn prepare_mesh_buffers(
+        &mut self,
+        instance: &Instance,
+        device: &Device,
+        physical_device: &PhysicalDevice,
+        command_pool: &CommandPool,
+        graphics_queue: &Queue,
+    ) -> Result<()> {
+        for mesh_buffer in self.buffers.iter_mut() {
+            if !mesh_buffer.is_prepared() {
+                mesh_buffer.prepare(
+                    instance,
+                    device,
+                    physical_device,
+                    command_pool,
+                    graphics_queue,
+                    &self.meshes,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if some atlas or material has to be (re)created on the GPU.
+    pub fn needs_prepare(&self) -> bool {
+        self.atlases.iter().any(|a| !a.is_prepared())
+            || self.materials.iter().any(|m| !m.is_prepared())
+    }
+
+    pub unsafe fn prepare_atlases(
+        &mut self,
+        instance: &Instance,
+        device: &Device,
+        physical_device: &PhysicalDevice,
+        command_pool: &CommandPool,
+        graphics_queue: &Queue,
+    ) -> Result<()> {
+        for atlas in self.atlases.iter_mut() {
+            if !atlas.is_prepared() {
+                atlas.prepare(
+                    instance,
+                    device,
+                    physical_device,
+                    command_pool,
+                    graphics_queue,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the descriptor sets of the materials which changed since the last call.
+    ///
+    /// Materials without a texture, or whose atlas is gone, bind `fallback_atlas`.
+    pub unsafe fn prepare_materials(
+        &mut self,
+        device: &Device,
+        layout: DescriptorSetLayout,
+        fallback_atlas: &Atlas,
+    ) -> Result<()> {
+        for material in self.materials.iter_mut() {
+            if !material.is_prepared() {
+                let atlas = material
+                    .get_texture()
+                    .and_then(|t| self.atlases.get(t.get_atlas()))
+                    .filter(|a| a.is_prepared())
+                    .unwrap_or(fallback_atlas);
+                material.prepare(device, layout, atlas)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn destroy(&mut self, device: &Device) {
         for mesh_buffer in self.buffers.iter_mut() {
             mesh_buffer.cleanup(device);
         }
+        for mut material in self.materials.take_replaced() {
+            material.cleanup(device);
+        }
+        for material in self.materials.iter_mut() {
+            material.cleanup(device);
+        }
+        for mut atlas in self.atlases.take_replaced() {
+            atlas.cleanup(device);
+        }
+        for atlas in self.atlases.iter_mut() {
+            atlas.cleanup(device);
+        }
     }
 }
 
@@ -111,3 +588,413 @@ impl Default for Scene {
         Self::new()
     }
 }
+
+/// Returns the smallest box containing the transformed corners of a box.
+fn transform_bounds(transform: Mat4, min: Point3, max: Point3) -> (Point3, Point3) {
+    let mut bounds: Option<(Point3, Point3)> = None;
+    for corner in 0..8 {
+        let local = Point3::new(
+            if corner & 1 == 0 { min.x } else { max.x },
+            if corner & 2 == 0 { min.y } else { max.y },
+            if corner & 4 == 0 { min.z } else { max.z },
+        );
+        let p = Point3::from_homogeneous(transform * local.to_homogeneous());
+        bounds = Some(match bounds {
+            Some((lo, hi)) => (
+                Point3::new(lo.x.min(p.x), lo.y.min(p.y), lo.z.min(p.z)),
+                Point3::new(hi.x.max(p.x), hi.y.max(p.y), hi.z.max(p.z)),
+            ),
+            None => (p, p),
+        });
+    }
+    bounds.unwrap_or((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::math::{Vec3, Vec4};
+
+    fn world_position(scene: &Scene, handle: Handle<Node>) -> Vec4 {
+        scene
+            .get_node(handle)
+            .unwrap()
+            .get_global_transform()
+            .unwrap()
+            * Vec4::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    #[test]
+    fn test_update_transforms() {
+        let mut scene = Scene::new();
+        let root = scene.create_root_node(Some("root".to_string())).unwrap();
+        let child = scene.create_node(Some("child".to_string()), root).unwrap();
+        let grandchild = scene
+            .create_node(Some("grandchild".to_string()), child)
+            .unwrap();
+
+        scene
+            .get_node_mut(root)
+            .unwrap()
+            .set_translation(Vec3::new(1.0, 0.0, 0.0));
+        scene
+            .get_node_mut(child)
+            .unwrap()
+            .set_scale(Vec3::new(2.0, 2.0, 2.0));
+        scene
+            .get_node_mut(grandchild)
+            .unwrap()
+            .set_translation(Vec3::new(0.0, 1.0, 0.0));
+        scene.update_transforms();
+
+        assert_eq!(
+            world_position(&scene, grandchild),
+            Vec4::new(1.0, 2.0, 0.0, 1.0)
+        );
+
+        // Moving the root moves its whole subtree.
+        scene
+            .get_node_mut(root)
+            .unwrap()
+            .set_translation(Vec3::new(0.0, 0.0, 3.0));
+        scene.update_transforms();
+
+        assert_eq!(world_position(&scene, child), Vec4::new(0.0, 0.0, 3.0, 1.0));
+        assert_eq!(
+            world_position(&scene, grandchild),
+            Vec4::new(0.0, 2.0, 3.0, 1.0)
+        );
+    }
+
+    fn build_tree(scene: &mut Scene) -> (Handle<Node>, Handle<Node>, Handle<Node>, Handle<Node>) {
+        let root = scene
+            .create_root_node(Some("Scene root".to_string()))
+            .unwrap();
+        let room = scene.create_node(Some("Room".to_string()), root).unwrap();
+        let chair = scene.create_node(Some("Chair".to_string()), room).unwrap();
+        let lamp = scene.create_node(Some("Lamp".to_string()), root).unwrap();
+        (root, room, chair, lamp)
+    }
+
+    #[test]
+    fn test_disconnect_node() {
+        let mut scene = Scene::new();
+        let (root, room, _, _) = build_tree(&mut scene);
+
+        scene.disconnect_node(room).unwrap();
+
+        assert!(scene.get_node(room).unwrap().is_root());
+        assert!(!scene
+            .get_node(root)
+            .unwrap()
+            .get_childreen()
+            .contains(&room));
+        assert_eq!(scene.get_root_nodes().len(), 2);
+    }
+
+    #[test]
+    fn test_reparent() {
+        let mut scene = Scene::new();
+        let (root, room, chair, lamp) = build_tree(&mut scene);
+
+        scene.reparent(lamp, chair).unwrap();
+        assert_eq!(scene.get_node(lamp).unwrap().get_parent(), Some(chair));
+        assert!(!scene
+            .get_node(root)
+            .unwrap()
+            .get_childreen()
+            .contains(&lamp));
+        assert_eq!(scene.find_by_path("Scene root/Room/Chair/Lamp"), Some(lamp));
+
+        // A node cannot be moved below itself or one of its descendants.
+        assert!(scene.reparent(room, room).is_err());
+        assert!(scene.reparent(room, lamp).is_err());
+        assert_eq!(scene.get_node(room).unwrap().get_parent(), Some(root));
+    }
+
+    #[test]
+    fn test_remove_node_recursive() {
+        let mut scene = Scene::new();
+        let (root, room, chair, lamp) = build_tree(&mut scene);
+
+        scene.remove_node_recursive(room).unwrap();
+
+        assert!(scene.get_node(room).is_none());
+        assert!(scene.get_node(chair).is_none());
+        assert!(scene.get_node(lamp).is_some());
+        assert_eq!(scene.get_node(root).unwrap().get_childreen(), &[lamp]);
+        assert!(scene.remove_node_recursive(room).is_err());
+    }
+
+    #[test]
+    fn test_traversal() {
+        let mut scene = Scene::new();
+        let (root, room, chair, lamp) = build_tree(&mut scene);
+        scene
+            .get_node_mut(room)
+            .unwrap()
+            .set_translation(Vec3::new(1.0, 0.0, 0.0));
+        scene
+            .get_node_mut(chair)
+            .unwrap()
+            .set_translation(Vec3::new(0.0, 1.0, 0.0));
+
+        let depth_first = scene
+            .iter_depth_first()
+            .map(|(handle, depth, _)| (handle, depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            depth_first,
+            vec![(root, 0), (room, 1), (chair, 2), (lamp, 1)]
+        );
+
+        let (_, _, chair_transform) = scene.iter_depth_first().nth(2).unwrap();
+        assert_eq!(
+            chair_transform * Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 1.0, 0.0, 1.0)
+        );
+
+        let breadth_first = scene
+            .iter_breadth_first()
+            .map(|(handle, depth, _)| (handle, depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            breadth_first,
+            vec![(root, 0), (room, 1), (lamp, 1), (chair, 2)]
+        );
+
+        let subtree = scene.iter_depth_first_from(room).collect::<Vec<_>>();
+        assert_eq!(subtree.len(), 2);
+        assert_eq!((subtree[1].0, subtree[1].1), (chair, 1));
+        assert_eq!(
+            subtree[1].2 * Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 1.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_find_by_path() {
+        let mut scene = Scene::new();
+        let (root, _, chair, _) = build_tree(&mut scene);
+
+        assert_eq!(scene.find_by_path("Scene root"), Some(root));
+        assert_eq!(scene.find_by_path("Scene root/Room/Chair"), Some(chair));
+        assert_eq!(scene.find_by_path("Scene root/Chair"), None);
+        assert_eq!(scene.find_by_path("Room"), None);
+    }
+
+    #[test]
+    fn test_child_order() {
+        let mut scene = Scene::new();
+        let root = scene.create_root_node(None).unwrap();
+        let a = scene.create_node(Some("a".to_string()), root).unwrap();
+        let b = scene.create_node(Some("b".to_string()), root).unwrap();
+        let c = scene.create_node(Some("c".to_string()), root).unwrap();
+        let childreen = |scene: &Scene| scene.get_node(root).unwrap().get_childreen().to_vec();
+
+        assert_eq!(childreen(&scene), vec![a, b, c]);
+
+        scene.move_node_before(c, a).unwrap();
+        assert_eq!(childreen(&scene), vec![c, a, b]);
+
+        scene.move_node_after(c, b).unwrap();
+        assert_eq!(childreen(&scene), vec![a, b, c]);
+
+        scene.set_node_index(a, 2).unwrap();
+        assert_eq!(childreen(&scene), vec![b, c, a]);
+
+        assert!(scene.set_node_index(a, 3).is_err());
+        assert!(scene.move_node_before(root, a).is_err());
+
+        // Reparented nodes are appended after the existing childreen.
+        let d = scene.create_node(None, a).unwrap();
+        scene.reparent(d, root).unwrap();
+        assert_eq!(childreen(&scene), vec![b, c, a, d]);
+
+        let order = scene
+            .iter_depth_first()
+            .map(|(handle, _, _)| handle)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![root, b, c, a, d]);
+    }
+
+    #[test]
+    fn test_root_order() {
+        let mut scene = Scene::new();
+        let a = scene.create_root_node(None).unwrap();
+        let b = scene.create_root_node(None).unwrap();
+        let c = scene.create_node(None, b).unwrap();
+
+        // A new root takes the freed slot of a removed one, but still comes last.
+        scene.remove_node_recursive(a).unwrap();
+        let d = scene.create_root_node(None).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![b, d]);
+
+        scene.disconnect_node(c).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![b, d, c]);
+
+        scene.move_node_before(c, b).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![c, b, d]);
+        scene.move_node_after(c, d).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![b, d, c]);
+        scene.set_node_index(d, 0).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![d, b, c]);
+
+        scene.reparent(b, c).unwrap();
+        assert_eq!(scene.get_root_nodes(), vec![d, c]);
+        let order = scene
+            .iter_depth_first()
+            .map(|(handle, _, _)| handle)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![d, c, b]);
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut scene = Scene::new();
+        let (root, room, chair, lamp) = build_tree(&mut scene);
+        scene.get_node_mut(room).unwrap().set_visible(false);
+
+        assert!(scene.is_node_visible(root));
+        assert!(!scene.is_node_visible(room));
+        assert!(!scene.is_node_visible(chair));
+        assert!(scene.get_node(chair).unwrap().is_visible());
+
+        let visible = |iter: DepthFirstNodeIter| iter.map(|(h, _, _)| h).collect::<Vec<_>>();
+        assert_eq!(
+            visible(scene.iter_depth_first().visible_only()),
+            vec![root, lamp]
+        );
+        assert!(visible(scene.iter_depth_first_from(chair).visible_only()).is_empty());
+    }
+
+    #[test]
+    fn test_node_material() {
+        let mut scene = Scene::new();
+        let (root, room, _, _) = build_tree(&mut scene);
+        let atlas = scene
+            .add_atlas(Atlas::new_solid(None, [255, 0, 0, 255]))
+            .unwrap();
+        let material = scene
+            .add_material(Material::with_texture(
+                Some("Red".to_string()),
+                Texture::full_atlas(atlas),
+            ))
+            .unwrap();
+
+        scene.node_set_material(room, material).unwrap();
+        assert_eq!(scene.get_node(room).unwrap().get_material(), Some(material));
+        assert_eq!(scene.get_node(root).unwrap().get_material(), None);
+        assert!(scene.needs_prepare());
+
+        scene.materials.remove(material);
+        assert!(scene.node_set_material(root, material).is_err());
+    }
+
+    #[test]
+    fn test_raycast() {
+        use cgmath::InnerSpace;
+
+        use crate::feather::math::Vec2;
+        use crate::feather::vertex::Vertex;
+
+        let mut scene = Scene::new();
+        let (root, room, chair, lamp) = build_tree(&mut scene);
+        let corner =
+            |x: f32, y: f32| Vertex::new(Vec3::new(x, y, 0.0), Vec3::unit_z(), Vec2::new(x, y));
+        let quad = Mesh::new(
+            None,
+            vec![
+                corner(0.0, 0.0),
+                corner(1.0, 0.0),
+                corner(1.0, 1.0),
+                corner(0.0, 1.0),
+            ],
+            vec![0, 1, 2, 2, 3, 0],
+        );
+        let quad = scene.add_mesh(quad).unwrap();
+        for node in [room, chair, lamp] {
+            scene.node_set_mesh(node, quad).unwrap();
+        }
+        // The chair is one unit above the room, the lamp two units above but hidden.
+        scene
+            .get_node_mut(chair)
+            .unwrap()
+            .set_translation(Vec3::new(0.0, 0.0, 1.0));
+        scene
+            .get_node_mut(lamp)
+            .unwrap()
+            .set_translation(Vec3::new(0.0, 0.0, 2.0));
+        scene.get_node_mut(lamp).unwrap().set_visible(false);
+
+        let ray = Ray::new(Point3::new(0.25, 0.75, 5.0), -Vec3::unit_z());
+        let hit = scene.raycast(&ray).unwrap();
+        assert_eq!(hit.node, chair);
+        assert_eq!(hit.triangle, 1);
+        assert!((hit.distance - 4.0).abs() < 1e-5);
+        assert!((hit.point - Point3::new(0.25, 0.75, 1.0)).magnitude() < 1e-5);
+        assert!((hit.barycentrics - Vec3::new(0.25, 0.5, 0.25)).magnitude() < 1e-5);
+
+        // Moving the chair away exposes the room below.
+        scene
+            .get_node_mut(chair)
+            .unwrap()
+            .set_translation(Vec3::new(5.0, 0.0, 1.0));
+        assert_eq!(scene.raycast(&ray).unwrap().node, room);
+        scene.get_node_mut(root).unwrap().set_visible(false);
+        assert!(scene.raycast(&ray).is_none());
+    }
+
+    #[test]
+    fn test_mesh_buffer_layouts() {
+        use crate::feather::meshbuilder::MeshBuilder;
+        use crate::feather::meshbuilderplane::MeshBuilderPlane;
+
+        let mut scene = Scene::new();
+        let plane = MeshBuilderPlane::new(1.0, 1.0);
+        let shaded = plane.build_into(&mut scene).unwrap();
+        let mut depth_only = plane.build_mesh().unwrap();
+        depth_only.set_layout(VertexLayout::position_only());
+        let depth_only = scene.add_mesh(depth_only).unwrap();
+        let other = plane.build_into(&mut scene).unwrap();
+        scene.build_missing_mesh_buffers().unwrap();
+
+        // One buffer per layout, each packing its meshes after one another.
+        assert_eq!(scene.buffers.iter().count(), 2);
+        let buffer_data = |mesh: Handle<Mesh>| {
+            scene
+                .get_mesh(mesh)
+                .unwrap()
+                .mesh_buffer_data
+                .as_ref()
+                .unwrap()
+        };
+        assert_eq!(
+            buffer_data(shaded).buffer_handle,
+            buffer_data(other).buffer_handle
+        );
+        assert_ne!(
+            buffer_data(shaded).buffer_handle,
+            buffer_data(depth_only).buffer_handle
+        );
+        assert_eq!(buffer_data(other).vertex_begin_index, 4);
+        let depth_buffer = scene.buffers.get(buffer_data(depth_only).buffer_handle);
+        assert_eq!(depth_buffer.unwrap().data_size_for_vertexes(), 4 * 12);
+
+        let mut mesh = plane.build_mesh().unwrap();
+        let mut buffer = MeshBuffer::new(VertexLayout::position_only());
+        assert!(buffer.add_mesh(&mut mesh).is_err());
+
+        // Empty meshes get no buffer, as Vulkan buffers cannot be empty.
+        let empty = scene
+            .add_mesh(Mesh::new(None, Vec::new(), Vec::new()))
+            .unwrap();
+        scene.build_missing_mesh_buffers().unwrap();
+        assert!(!scene.get_mesh(empty).unwrap().has_buffers_assigned());
+        assert_eq!(scene.buffers.iter().count(), 2);
+        let mut empty = Mesh::new(None, Vec::new(), Vec::new());
+        assert!(MeshBuffer::new(VertexLayout::default())
+            .add_mesh(&mut empty)
+            .is_err());
+    }
+}
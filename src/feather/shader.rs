@@ -1,3 +1,99 @@
-pub struct Shader {}
+use super::vertexlayout::{VertexAttribute, VertexLayout};
 
-impl Shader {}
+/// A vertex and fragment shader pair in SPIR-V.
+///
+/// Shaders are compiled into pipelines on first use and cached by name, so two shaders with
+/// different code must have different names. Custom shaders have to use the same descriptor
+/// sets and push constants as the built-in one, and read vertex inputs at the locations of
+/// `VertexAttribute`. Meshes whose layout lacks an attribute the shader reads are not drawn
+/// with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    name: String,
+    vertex_code: Vec<u8>,
+    fragment_code: Vec<u8>,
+    attributes: Vec<VertexAttribute>,
+}
+
+impl Shader {
+    /// Creates a shader reading the position, normal and texture coordinates of the vertices,
+    /// like the built-in one.
+    pub fn new(name: &str, vertex_code: &[u8], fragment_code: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            vertex_code: vertex_code.to_vec(),
+            fragment_code: fragment_code.to_vec(),
+            attributes: vec![
+                VertexAttribute::Position,
+                VertexAttribute::Normal,
+                VertexAttribute::TexCoord,
+            ],
+        }
+    }
+
+    /// Sets the vertex attributes the vertex shader reads.
+    pub fn set_attributes(mut self, attributes: &[VertexAttribute]) -> Self {
+        self.attributes = attributes.to_vec();
+        self
+    }
+
+    pub fn get_attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    /// Returns the attributes the shader reads which the layout does not contain.
+    pub fn missing_attributes(&self, layout: &VertexLayout) -> Vec<VertexAttribute> {
+        self.attributes
+            .iter()
+            .copied()
+            .filter(|a| !layout.contains(*a))
+            .collect()
+    }
+
+    /// Returns whether meshes with the layout can be drawn with the shader.
+    pub fn supports(&self, layout: &VertexLayout) -> bool {
+        self.missing_attributes(layout).is_empty()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_vertex_code(&self) -> &[u8] {
+        &self.vertex_code
+    }
+
+    pub fn get_fragment_code(&self) -> &[u8] {
+        &self.fragment_code
+    }
+}
+
+impl Default for Shader {
+    /// The built-in textured shader.
+    fn default() -> Self {
+        Self::new(
+            "default",
+            include_bytes!("../../shaders/26/vert.spv"),
+            include_bytes!("../../shaders/26/frag.spv"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports() {
+        let shader = Shader::default();
+        assert!(shader.supports(&VertexLayout::default()));
+        assert_eq!(
+            shader.missing_attributes(&VertexLayout::position_only()),
+            [VertexAttribute::Normal, VertexAttribute::TexCoord]
+        );
+
+        let shader = shader.set_attributes(&[VertexAttribute::Position]);
+        assert!(shader.supports(&VertexLayout::position_only()));
+        assert!(!shader.supports(&VertexLayout::new(&[VertexAttribute::Normal])));
+    }
+}
@@ -5,7 +5,7 @@ use winit::window::Window;
 use vulkanalia::vk::KhrSwapchainExtension;
 
 use super::appdata::AppData;
-use super::images::create_image_view;
+use super::images::{create_image, create_image_view};
 use super::queuefamilyindices::QueueFamilyIndices;
 use super::swapchainsupport::SwapchainSupport;
 
@@ -16,8 +16,15 @@ pub struct Swapchain {
     pub swapchain: vk::SwapchainKHR,
     pub swapchain_images: Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
+    /// The memory of the images of an offscreen swapchain.
+    pub offscreen_images_memory: Vec<vk::DeviceMemory>,
+    /// Whether the images can be copied back to the CPU.
+    pub readable: bool,
 }
 
+/// The format of offscreen images, chosen so they can be read back as RGBA8.
+const OFFSCREEN_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
 fn get_swapchain_surface_format(formats: &[vk::SurfaceFormatKHR]) -> vk::SurfaceFormatKHR {
     formats
         .iter()
@@ -81,6 +88,17 @@ impl Swapchain {
             image_count = support.capabilities.max_image_count;
         }
 
+        // Frame captures copy from the swapchain images, which not every surface allows.
+        let readable = support
+            .capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_SRC);
+        let image_usage = if readable {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
+        } else {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+        };
+
         let mut queue_family_indices = vec![];
         let image_sharing_mode = if indices.graphics != indices.present {
             queue_family_indices.push(indices.graphics);
@@ -99,7 +117,7 @@ impl Swapchain {
             .image_color_space(surface_format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(support.capabilities.current_transform)
@@ -120,9 +138,47 @@ impl Swapchain {
             swapchain,
             swapchain_images,
             swapchain_image_views: vec![],
+            offscreen_images_memory: vec![],
+            readable,
         })
     }
 
+    /// Creates a single offscreen image standing in for the swapchain when rendering headless.
+    pub unsafe fn create_offscreen(
+        instance: &Instance,
+        device: &Device,
+        data: &AppData,
+        extent: vk::Extent2D,
+    ) -> Result<Self> {
+        let (image, image_memory) = create_image(
+            instance,
+            device,
+            &data.physical_device,
+            extent.width,
+            extent.height,
+            1,
+            vk::SampleCountFlags::_1,
+            OFFSCREEN_FORMAT,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+
+        Ok(Self {
+            swapchain_format: OFFSCREEN_FORMAT,
+            swapchain_extent: extent,
+            swapchain: vk::SwapchainKHR::null(),
+            swapchain_images: vec![image],
+            swapchain_image_views: vec![],
+            offscreen_images_memory: vec![image_memory],
+            readable: true,
+        })
+    }
+
+    pub fn is_offscreen(&self) -> bool {
+        !self.offscreen_images_memory.is_empty()
+    }
+
     pub unsafe fn create_image_views(&mut self, device: &Device) -> Result<()> {
         self.swapchain_image_views = self
             .swapchain_images
@@ -147,7 +203,18 @@ impl Swapchain {
                 device.destroy_image_view(*image_view, None);
             }
 
-            device.destroy_swapchain_khr(self.swapchain, None);
+            if self.is_offscreen() {
+                for image in &self.swapchain_images {
+                    device.destroy_image(*image, None);
+                }
+                for memory in &self.offscreen_images_memory {
+                    device.free_memory(*memory, None);
+                }
+                self.swapchain_images.clear();
+                self.offscreen_images_memory.clear();
+            } else {
+                device.destroy_swapchain_khr(self.swapchain, None);
+            }
             self.swapchain_image_views.clear();
             self.swapchain = vk::SwapchainKHR::null();
         }
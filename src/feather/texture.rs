@@ -1,20 +1,11 @@
-use std::fs::File;
-use std::ptr::copy_nonoverlapping as memcpy;
-use std::rc::Rc;
-
-use anyhow::{anyhow, Result};
-use vulkanalia::prelude::v1_0::*;
-
-use super::appdata::AppData;
 use super::atlas::Atlas;
-use super::buffers::create_buffer;
-use super::images::{
-    copy_buffer_to_image, create_image, create_image_view, transition_image_layout,
-};
-use super::other::{begin_single_time_commands, end_single_time_commands};
+use super::handle::Handle;
+use super::math::Vec2;
 
+/// A rectangular window of an `Atlas`, in normalized texture coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Texture {
-    atlas: Rc<Atlas>,
+    atlas: Handle<Atlas>,
     u_min: f32,
     v_min: f32,
     u_range: f32,
@@ -22,307 +13,53 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn full_atlas(atlas: Rc<Atlas>) -> Self {
+    pub fn new(atlas: Handle<Atlas>, u_min: f32, v_min: f32, u_range: f32, v_range: f32) -> Self {
         Self {
             atlas,
-            u_min: 0.0,
-            v_min: 0.0,
-            u_range: 1.0,
-            v_range: 1.0,
+            u_min,
+            v_min,
+            u_range,
+            v_range,
         }
     }
-}
-
-//================================================
-// Texture
-//================================================
-
-pub unsafe fn create_texture_image(
-    instance: &Instance,
-    device: &Device,
-    data: &mut AppData,
-) -> Result<()> {
-    // Load
-
-    let image = File::open("resources/viking_room.png")?;
-
-    let decoder = png::Decoder::new(image);
-    let mut reader = decoder.read_info()?;
 
-    let mut pixels = vec![0; reader.info().raw_bytes()];
-    reader.next_frame(&mut pixels)?;
-
-    let size = reader.info().raw_bytes() as u64;
-    let (width, height) = reader.info().size();
-    data.mip_levels = (width.max(height) as f32).log2().floor() as u32 + 1;
-
-    if width != 1024 || height != 1024 || reader.info().color_type != png::ColorType::Rgba {
-        panic!("Invalid texture image (use https://kylemayes.github.io/vulkanalia/images/viking_room.png).");
+    pub fn full_atlas(atlas: Handle<Atlas>) -> Self {
+        Self::new(atlas, 0.0, 0.0, 1.0, 1.0)
     }
 
-    // Create (staging)
-
-    let (staging_buffer, staging_buffer_memory) = create_buffer(
-        instance,
-        device,
-        &data.physical_device,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    )?;
-
-    // Copy (staging)
-
-    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
-
-    memcpy(pixels.as_ptr(), memory.cast(), pixels.len());
-
-    device.unmap_memory(staging_buffer_memory);
-
-    // Create (image)
-
-    let (texture_image, texture_image_memory) = create_image(
-        instance,
-        device,
-        data,
-        width,
-        height,
-        data.mip_levels,
-        vk::SampleCountFlags::_1,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageTiling::OPTIMAL,
-        vk::ImageUsageFlags::SAMPLED
-            | vk::ImageUsageFlags::TRANSFER_DST
-            | vk::ImageUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )?;
-
-    data.texture_image = texture_image;
-    data.texture_image_memory = texture_image_memory;
-
-    // Transition + Copy (image)
-
-    transition_image_layout(
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageLayout::UNDEFINED,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        data.mip_levels,
-    )?;
-
-    copy_buffer_to_image(
-        device,
-        data,
-        staging_buffer,
-        data.texture_image,
-        width,
-        height,
-    )?;
-
-    // Cleanup
-
-    device.destroy_buffer(staging_buffer, None);
-    device.free_memory(staging_buffer_memory, None);
-
-    // Mipmaps
-
-    generate_mipmaps(
-        instance,
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        width,
-        height,
-        data.mip_levels,
-    )?;
-
-    Ok(())
-}
-
-unsafe fn generate_mipmaps(
-    instance: &Instance,
-    device: &Device,
-    data: &AppData,
-    image: vk::Image,
-    format: vk::Format,
-    width: u32,
-    height: u32,
-    mip_levels: u32,
-) -> Result<()> {
-    // Support
-
-    if !instance
-        .get_physical_device_format_properties(data.physical_device, format)
-        .optimal_tiling_features
-        .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
-    {
-        return Err(anyhow!(
-            "Texture image format does not support linear blitting!"
-        ));
+    pub fn get_atlas(&self) -> Handle<Atlas> {
+        self.atlas
     }
 
-    // Mipmaps
-
-    let command_buffer = begin_single_time_commands(device, &data.command_pool)?;
-
-    let subresource = vk::ImageSubresourceRange::builder()
-        .aspect_mask(vk::ImageAspectFlags::COLOR)
-        .base_array_layer(0)
-        .layer_count(1)
-        .level_count(1);
-
-    let mut barrier = vk::ImageMemoryBarrier::builder()
-        .image(image)
-        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .subresource_range(subresource);
-
-    let mut mip_width = width;
-    let mut mip_height = height;
-
-    for i in 1..mip_levels {
-        barrier.subresource_range.base_mip_level = i - 1;
-        barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
-        barrier.new_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
-        barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-        barrier.dst_access_mask = vk::AccessFlags::TRANSFER_READ;
-
-        device.cmd_pipeline_barrier(
-            command_buffer,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::DependencyFlags::empty(),
-            &[] as &[vk::MemoryBarrier],
-            &[] as &[vk::BufferMemoryBarrier],
-            &[barrier],
-        );
-
-        let src_subresource = vk::ImageSubresourceLayers::builder()
-            .aspect_mask(vk::ImageAspectFlags::COLOR)
-            .mip_level(i - 1)
-            .base_array_layer(0)
-            .layer_count(1);
-
-        let dst_subresource = vk::ImageSubresourceLayers::builder()
-            .aspect_mask(vk::ImageAspectFlags::COLOR)
-            .mip_level(i)
-            .base_array_layer(0)
-            .layer_count(1);
-
-        let blit = vk::ImageBlit::builder()
-            .src_offsets([
-                vk::Offset3D { x: 0, y: 0, z: 0 },
-                vk::Offset3D {
-                    x: mip_width as i32,
-                    y: mip_height as i32,
-                    z: 1,
-                },
-            ])
-            .src_subresource(src_subresource)
-            .dst_offsets([
-                vk::Offset3D { x: 0, y: 0, z: 0 },
-                vk::Offset3D {
-                    x: (if mip_width > 1 { mip_width / 2 } else { 1 }) as i32,
-                    y: (if mip_height > 1 { mip_height / 2 } else { 1 }) as i32,
-                    z: 1,
-                },
-            ])
-            .dst_subresource(dst_subresource);
-
-        device.cmd_blit_image(
-            command_buffer,
-            image,
-            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            image,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            &[blit],
-            vk::Filter::LINEAR,
-        );
-
-        barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
-        barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
-        barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
-        barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
-
-        device.cmd_pipeline_barrier(
-            command_buffer,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::PipelineStageFlags::FRAGMENT_SHADER,
-            vk::DependencyFlags::empty(),
-            &[] as &[vk::MemoryBarrier],
-            &[] as &[vk::BufferMemoryBarrier],
-            &[barrier],
-        );
-
-        if mip_width > 1 {
-            mip_width /= 2;
-        }
-
-        if mip_height > 1 {
-            mip_height /= 2;
-        }
+    /// Returns the (min, max) range of the window along u.
+    pub fn get_u(&self) -> (f32, f32) {
+        (self.u_min, self.u_min + self.u_range)
     }
 
-    barrier.subresource_range.base_mip_level = mip_levels - 1;
-    barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
-    barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
-    barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-    barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
-
-    device.cmd_pipeline_barrier(
-        command_buffer,
-        vk::PipelineStageFlags::TRANSFER,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::DependencyFlags::empty(),
-        &[] as &[vk::MemoryBarrier],
-        &[] as &[vk::BufferMemoryBarrier],
-        &[barrier],
-    );
-
-    end_single_time_commands(
-        device,
-        &data.command_pool,
-        &data.graphics_queue,
-        command_buffer,
-    )?;
-
-    Ok(())
-}
-
-pub unsafe fn create_texture_image_view(device: &Device, data: &mut AppData) -> Result<()> {
-    data.texture_image_view = create_image_view(
-        device,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageAspectFlags::COLOR,
-        data.mip_levels,
-    )?;
+    /// Returns the (min, max) range of the window along v.
+    pub fn get_v(&self) -> (f32, f32) {
+        (self.v_min, self.v_min + self.v_range)
+    }
 
-    Ok(())
+    /// Maps texture coordinates of the whole texture (0..1) into the window of the atlas.
+    pub fn map_uv(&self, uv: Vec2) -> Vec2 {
+        Vec2::new(
+            self.u_min + uv.x * self.u_range,
+            self.v_min + uv.y * self.v_range,
+        )
+    }
 }
 
-pub unsafe fn create_texture_sampler(device: &Device, data: &mut AppData) -> Result<()> {
-    let info = vk::SamplerCreateInfo::builder()
-        .mag_filter(vk::Filter::LINEAR)
-        .min_filter(vk::Filter::LINEAR)
-        .address_mode_u(vk::SamplerAddressMode::REPEAT)
-        .address_mode_v(vk::SamplerAddressMode::REPEAT)
-        .address_mode_w(vk::SamplerAddressMode::REPEAT)
-        .anisotropy_enable(true)
-        .max_anisotropy(16.0)
-        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-        .unnormalized_coordinates(false)
-        .compare_enable(false)
-        .compare_op(vk::CompareOp::ALWAYS)
-        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
-        .min_lod(0.0)
-        .max_lod(data.mip_levels as f32)
-        .mip_lod_bias(0.0);
-
-    data.texture_sampler = device.create_sampler(&info, None)?;
-
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_uv() {
+        let texture = Texture::new(Handle::invalid(), 0.5, 0.25, 0.25, 0.5);
+        assert_eq!(texture.map_uv(Vec2::new(0.0, 0.0)), Vec2::new(0.5, 0.25));
+        assert_eq!(texture.map_uv(Vec2::new(1.0, 1.0)), Vec2::new(0.75, 0.75));
+        assert_eq!(texture.get_u(), (0.5, 0.75));
+        assert_eq!(texture.get_v(), (0.25, 0.75));
+    }
 }
@@ -0,0 +1,166 @@
+use std::fs;
+
+use thiserror::Error;
+
+use super::atlas::Atlas;
+use super::objdb::ObjDBError;
+
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("Failed to read texture `{0}`: {1}")]
+    Io(String, std::io::Error),
+    #[error("Failed to decode texture: {0}")]
+    Decode(#[from] png::DecodingError),
+    #[error("Failed to encode image: {0}")]
+    Encode(#[from] png::EncodingError),
+    #[error("Unsupported texture format {0:?} with bit depth {1:?}.")]
+    UnsupportedFormat(png::ColorType, png::BitDepth),
+    #[error("Texture size {0}x{1} does not match its pixel data.")]
+    InvalidSize(u32, u32),
+    #[error("Images do not fit into an atlas of at most {0}x{0} pixels.")]
+    AtlasFull(u32),
+    #[error(transparent)]
+    ObjDB(#[from] ObjDBError),
+}
+
+/// Loads a PNG file into an atlas named after the path.
+pub fn load_png_file(path: &str) -> Result<Atlas, TextureError> {
+    let bytes = fs::read(path).map_err(|e| TextureError::Io(path.to_string(), e))?;
+    load_png_bytes(Some(path.to_string()), &bytes)
+}
+
+/// Decodes an in-memory PNG into an atlas.
+///
+/// Palette, grayscale and RGB images, with or without alpha and with 8 or 16 bits per channel,
+/// are converted to 8-bit RGBA.
+pub fn load_png_bytes(name: Option<String>, bytes: &[u8]) -> Result<Atlas, TextureError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = to_rgba8(info.color_type, info.bit_depth, &buffer)?;
+    Atlas::new(name, info.width, info.height, pixels)
+}
+
+/// Converts decoded scanlines to 8-bit RGBA; 16-bit channels keep their high byte.
+fn to_rgba8(
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    data: &[u8],
+) -> Result<Vec<u8>, TextureError> {
+    let bytes_per_channel = match bit_depth {
+        png::BitDepth::Eight => 1,
+        png::BitDepth::Sixteen => 2,
+        _ => return Err(TextureError::UnsupportedFormat(color_type, bit_depth)),
+    };
+    let channels = match color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(TextureError::UnsupportedFormat(color_type, bit_depth))
+        }
+    };
+
+    let pixel_size = channels * bytes_per_channel;
+    let mut pixels = Vec::with_capacity(data.len() / pixel_size * 4);
+    for pixel in data.chunks_exact(pixel_size) {
+        let channel = |i: usize| pixel[i * bytes_per_channel];
+        let rgba = match channels {
+            1 => [channel(0), channel(0), channel(0), 255],
+            2 => [channel(0), channel(0), channel(0), channel(1)],
+            3 => [channel(0), channel(1), channel(2), 255],
+            _ => [channel(0), channel(1), channel(2), channel(3)],
+        };
+        pixels.extend_from_slice(&rgba);
+    }
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(
+        width: u32,
+        height: u32,
+        color_type: png::ColorType,
+        bit_depth: png::BitDepth,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(bit_depth);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_load_formats() {
+        // A non-power-of-two RGB image.
+        let rgb = (0..3 * 5 * 3).map(|i| i as u8).collect::<Vec<_>>();
+        let bytes = encode(3, 5, png::ColorType::Rgb, png::BitDepth::Eight, &rgb);
+        let atlas = load_png_bytes(None, &bytes).unwrap();
+        assert_eq!((atlas.get_width(), atlas.get_height()), (3, 5));
+        assert_eq!(&atlas.get_pixels()[..8], &[0, 1, 2, 255, 3, 4, 5, 255]);
+
+        let gray16 = [0x12, 0x34, 0xab, 0xcd];
+        let bytes = encode(
+            2,
+            1,
+            png::ColorType::Grayscale,
+            png::BitDepth::Sixteen,
+            &gray16,
+        );
+        let atlas = load_png_bytes(None, &bytes).unwrap();
+        assert_eq!(
+            atlas.get_pixels(),
+            &[0x12, 0x12, 0x12, 255, 0xab, 0xab, 0xab, 255]
+        );
+
+        let gray_alpha = [7, 128];
+        let bytes = encode(
+            1,
+            1,
+            png::ColorType::GrayscaleAlpha,
+            png::BitDepth::Eight,
+            &gray_alpha,
+        );
+        let atlas = load_png_bytes(None, &bytes).unwrap();
+        assert_eq!(atlas.get_pixels(), &[7, 7, 7, 128]);
+
+        // Sub-byte grayscale is expanded to 8 bits by the decoder.
+        let bytes = encode(
+            8,
+            1,
+            png::ColorType::Grayscale,
+            png::BitDepth::One,
+            &[0b1000_0000],
+        );
+        let atlas = load_png_bytes(None, &bytes).unwrap();
+        assert_eq!(
+            &atlas.get_pixels()[..8],
+            &[255, 255, 255, 255, 0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(
+            load_png_bytes(None, b"not a png"),
+            Err(TextureError::Decode(_))
+        ));
+        assert!(matches!(
+            load_png_file("resources/does_not_exist.png"),
+            Err(TextureError::Io(..))
+        ));
+    }
+}
@@ -3,7 +3,6 @@ type Mat4 = cgmath::Matrix4<f32>;
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct UniformBufferObject {
-    pub model: Mat4,
     pub view: Mat4,
     pub proj: Mat4,
 }
@@ -1,16 +1,25 @@
 use std::hash::{Hash, Hasher};
-use std::mem::size_of;
 
-use vulkanalia::prelude::v1_0::*;
+use super::math::{Vec2, Vec3, Vec4};
 
-use super::math::{Vec2, Vec3};
-
-#[repr(C)]
+/// A vertex with every attribute a mesh can have. Which of them are uploaded to the GPU is
+/// decided by the `VertexLayout` of the mesh.
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
     pub pos: Vec3,
     pub normal: Vec3,
     pub tex_coord: Vec2,
+    /// The direction of growing u along the surface in xyz, and in w the sign of the bitangent
+    /// `w * normal.cross(tangent)`, as in MikkTSpace. All zero until computed.
+    pub tangent: Vec4,
+    /// White unless set.
+    pub color: Vec4,
+    /// The second texture coordinate set, e.g. for light maps.
+    pub tex_coord1: Vec2,
+    /// The indices of up to four joints moving the vertex.
+    pub joints: [u16; 4],
+    /// The weights of the joints, all zero unless set.
+    pub weights: Vec4,
 }
 
 impl Vertex {
@@ -19,43 +28,31 @@ impl Vertex {
             pos,
             normal,
             tex_coord,
+            tangent: Vec4::new(0.0, 0.0, 0.0, 0.0),
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            tex_coord1: Vec2::new(0.0, 0.0),
+            joints: [0; 4],
+            weights: Vec4::new(0.0, 0.0, 0.0, 0.0),
         }
     }
 
-    pub fn binding_description() -> vk::VertexInputBindingDescription {
-        vk::VertexInputBindingDescription::builder()
-            .binding(0)
-            .stride(size_of::<Vertex>() as u32)
-            .input_rate(vk::VertexInputRate::VERTEX)
-            .build()
-    }
-
-    pub fn attribute_descriptions() -> [vk::VertexInputAttributeDescription; 3] {
-        let pos = vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(0)
-            .format(vk::Format::R32G32B32_SFLOAT)
-            .offset(0)
-            .build();
-        let normal = vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(1)
-            .format(vk::Format::R32G32B32_SFLOAT)
-            .offset(size_of::<Vec3>() as u32)
-            .build();
-        let tex_coord = vk::VertexInputAttributeDescription::builder()
-            .binding(0)
-            .location(2)
-            .format(vk::Format::R32G32_SFLOAT)
-            .offset((size_of::<Vec3>() + size_of::<Vec3>()) as u32)
-            .build();
-        [pos, normal, tex_coord]
+    /// All float components, in the order of the fields.
+    fn floats(&self) -> impl Iterator<Item = f32> + '_ {
+        self.pos[..]
+            .iter()
+            .chain(&self.normal[..])
+            .chain(&self.tex_coord[..])
+            .chain(&self.tangent[..])
+            .chain(&self.color[..])
+            .chain(&self.tex_coord1[..])
+            .chain(&self.weights[..])
+            .copied()
     }
 }
 
 impl PartialEq for Vertex {
     fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos && self.normal == other.normal && self.tex_coord == other.tex_coord
+        self.floats().eq(other.floats()) && self.joints == other.joints
     }
 }
 
@@ -63,13 +60,9 @@ impl Eq for Vertex {}
 
 impl Hash for Vertex {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.pos[0].to_bits().hash(state);
-        self.pos[1].to_bits().hash(state);
-        self.pos[2].to_bits().hash(state);
-        self.normal[0].to_bits().hash(state);
-        self.normal[1].to_bits().hash(state);
-        self.normal[2].to_bits().hash(state);
-        self.tex_coord[0].to_bits().hash(state);
-        self.tex_coord[1].to_bits().hash(state);
+        for value in self.floats() {
+            value.to_bits().hash(state);
+        }
+        self.joints.hash(state);
     }
 }
@@ -0,0 +1,233 @@
+use vulkanalia::prelude::v1_0::*;
+
+use super::vertex::Vertex;
+
+/// A vertex attribute which a mesh can upload to the GPU.
+///
+/// Every attribute has a fixed shader input location, so a shader reads it at the same
+/// location whatever else the layout contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    /// `vec3` at location 0.
+    Position,
+    /// `vec3` at location 1.
+    Normal,
+    /// `vec2` at location 2.
+    TexCoord,
+    /// `vec4` at location 3.
+    Tangent,
+    /// `vec4` at location 4.
+    Color,
+    /// The second texture coordinate set, `vec2` at location 5.
+    TexCoord1,
+    /// Four joint indices, `uvec4` at location 6.
+    Joints,
+    /// Four joint weights, `vec4` at location 7.
+    Weights,
+}
+
+impl VertexAttribute {
+    pub fn get_location(&self) -> u32 {
+        match self {
+            Self::Position => 0,
+            Self::Normal => 1,
+            Self::TexCoord => 2,
+            Self::Tangent => 3,
+            Self::Color => 4,
+            Self::TexCoord1 => 5,
+            Self::Joints => 6,
+            Self::Weights => 7,
+        }
+    }
+
+    pub fn get_format(&self) -> vk::Format {
+        match self {
+            Self::Position | Self::Normal => vk::Format::R32G32B32_SFLOAT,
+            Self::TexCoord | Self::TexCoord1 => vk::Format::R32G32_SFLOAT,
+            Self::Tangent | Self::Color | Self::Weights => vk::Format::R32G32B32A32_SFLOAT,
+            Self::Joints => vk::Format::R16G16B16A16_UINT,
+        }
+    }
+
+    /// Returns the size of the attribute in bytes.
+    pub fn get_size(&self) -> usize {
+        match self {
+            Self::Position | Self::Normal => 12,
+            Self::TexCoord | Self::TexCoord1 => 8,
+            Self::Tangent | Self::Color | Self::Weights => 16,
+            Self::Joints => 8,
+        }
+    }
+
+    /// Appends the value of the attribute in a vertex to the packed bytes.
+    fn write(&self, vertex: &Vertex, bytes: &mut Vec<u8>) {
+        let floats: &[f32] = match self {
+            Self::Position => &vertex.pos[..],
+            Self::Normal => &vertex.normal[..],
+            Self::TexCoord => &vertex.tex_coord[..],
+            Self::Tangent => &vertex.tangent[..],
+            Self::Color => &vertex.color[..],
+            Self::TexCoord1 => &vertex.tex_coord1[..],
+            Self::Weights => &vertex.weights[..],
+            Self::Joints => {
+                bytes.extend(vertex.joints.iter().flat_map(|j| j.to_ne_bytes()));
+                return;
+            }
+        };
+        bytes.extend(floats.iter().flat_map(|f| f.to_ne_bytes()));
+    }
+}
+
+/// The attributes a mesh uploads for each vertex, interleaved in the given order.
+///
+/// Meshes with the same layout share mesh buffers, and every shader gets a pipeline per layout
+/// it is drawn with. The layout has to contain all inputs the shader reads.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+}
+
+impl VertexLayout {
+    /// Creates a layout of the given attributes; repeated ones are only stored once.
+    pub fn new(attributes: &[VertexAttribute]) -> Self {
+        let mut unique = Vec::new();
+        for attribute in attributes {
+            if !unique.contains(attribute) {
+                unique.push(*attribute);
+            }
+        }
+        Self { attributes: unique }
+    }
+
+    /// A layout with positions alone, e.g. for shadow or depth passes.
+    pub fn position_only() -> Self {
+        Self::new(&[VertexAttribute::Position])
+    }
+
+    pub fn get_attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    pub fn contains(&self, attribute: VertexAttribute) -> bool {
+        self.attributes.contains(&attribute)
+    }
+
+    /// Returns the size of one packed vertex in bytes.
+    pub fn get_stride(&self) -> usize {
+        self.attributes.iter().map(|a| a.get_size()).sum()
+    }
+
+    /// Returns the offset of an attribute within a packed vertex, if the layout contains it.
+    pub fn get_offset(&self, attribute: VertexAttribute) -> Option<usize> {
+        let index = self.attributes.iter().position(|a| *a == attribute)?;
+        Some(self.attributes[..index].iter().map(|a| a.get_size()).sum())
+    }
+
+    pub fn binding_description(&self) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(self.get_stride() as u32)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build()
+    }
+
+    pub fn attribute_descriptions(&self) -> Vec<vk::VertexInputAttributeDescription> {
+        let mut offset = 0;
+        let mut descriptions = Vec::new();
+        for attribute in &self.attributes {
+            descriptions.push(
+                vk::VertexInputAttributeDescription::builder()
+                    .binding(0)
+                    .location(attribute.get_location())
+                    .format(attribute.get_format())
+                    .offset(offset as u32)
+                    .build(),
+            );
+            offset += attribute.get_size();
+        }
+        descriptions
+    }
+
+    /// Packs the attributes of the vertices, one vertex after the other.
+    pub fn pack(&self, vertices: &[Vertex]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.get_stride() * vertices.len());
+        for vertex in vertices {
+            for attribute in &self.attributes {
+                attribute.write(vertex, &mut bytes);
+            }
+        }
+        bytes
+    }
+}
+
+impl Default for VertexLayout {
+    /// The layout read by the built-in shader, with tangents for normal mapping.
+    fn default() -> Self {
+        Self::new(&[
+            VertexAttribute::Position,
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoord,
+            VertexAttribute::Tangent,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feather::math::{Vec2, Vec3, Vec4};
+
+    #[test]
+    fn test_layouts() {
+        let layout = VertexLayout::default();
+        assert_eq!(layout.get_stride(), 48);
+        assert_eq!(layout.get_offset(VertexAttribute::TexCoord), Some(24));
+        assert_eq!(layout.get_offset(VertexAttribute::Color), None);
+        let descriptions = layout.attribute_descriptions();
+        assert_eq!(descriptions.len(), 4);
+        assert_eq!(descriptions[3].location, 3);
+        assert_eq!(descriptions[3].offset, 32);
+
+        // Locations follow the attribute, not its place in the layout.
+        let skinned = VertexLayout::new(&[
+            VertexAttribute::Weights,
+            VertexAttribute::Position,
+            VertexAttribute::Joints,
+            VertexAttribute::Position,
+        ]);
+        assert_eq!(skinned.get_attributes().len(), 3);
+        assert_eq!(skinned.get_stride(), 16 + 12 + 8);
+        assert_eq!(skinned.binding_description().stride, 36);
+        let descriptions = skinned.attribute_descriptions();
+        assert_eq!(
+            descriptions.iter().map(|d| d.location).collect::<Vec<_>>(),
+            [7, 0, 6]
+        );
+        assert_eq!(descriptions[2].offset, 28);
+        assert_eq!(descriptions[2].format, vk::Format::R16G16B16A16_UINT);
+        assert_ne!(skinned, VertexLayout::position_only());
+    }
+
+    #[test]
+    fn test_pack() {
+        let mut vertex = Vertex::new(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::unit_z(),
+            Vec2::new(0.5, 0.25),
+        );
+        vertex.color = Vec4::new(0.1, 0.2, 0.3, 0.4);
+        vertex.joints = [1, 2, 3, 513];
+        let vertices = [vertex, vertex];
+
+        let bytes = VertexLayout::position_only().pack(&vertices);
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(bytes[12..16], 1f32.to_ne_bytes());
+
+        let layout = VertexLayout::new(&[VertexAttribute::Color, VertexAttribute::Joints]);
+        let bytes = layout.pack(&vertices);
+        assert_eq!(bytes.len(), 2 * layout.get_stride());
+        assert_eq!(bytes[12..16], 0.4f32.to_ne_bytes());
+        assert_eq!(bytes[22..24], 513u16.to_ne_bytes());
+        assert_eq!(bytes[..24], bytes[24..]);
+    }
+}
@@ -0,0 +1,119 @@
+use vulkanalia::prelude::v1_0::*;
+
+use super::featherapp::FeatherApp;
+use super::math::Vec4;
+
+/// A rectangle of the screen a scene is rendered into, in normalized coordinates from the top
+/// left (0, 0) to the bottom right (1, 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The color the rectangle is cleared to before drawing, if any.
+    pub clear_color: Option<Vec4>,
+    /// Whether the depth of the rectangle is cleared before drawing, so the scene is drawn on
+    /// top of what was rendered before.
+    pub clear_depth: bool,
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            clear_color: None,
+            clear_depth: false,
+        }
+    }
+
+    /// Covers the whole screen.
+    pub fn full() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: Option<Vec4>) -> &mut Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    pub fn set_clear_depth(&mut self, clear_depth: bool) -> &mut Self {
+        self.clear_depth = clear_depth;
+        self
+    }
+
+    /// Lays out `count` viewports in a grid filling the screen, row by row.
+    pub fn grid(count: usize) -> Vec<Self> {
+        let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+        let rows = count.div_ceil(columns).max(1);
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        (0..count)
+            .map(|i| {
+                let (column, row) = (i % columns, i / columns);
+                Self::new(column as f32 * width, row as f32 * height, width, height)
+            })
+            .collect()
+    }
+
+    /// Returns the rectangle in pixels of a target of the given size, at least one pixel large.
+    pub fn to_pixels(self, extent: vk::Extent2D) -> vk::Rect2D {
+        let (w, h) = (extent.width as f32, extent.height as f32);
+        let x0 = (self.x * w).round().clamp(0.0, w - 1.0);
+        let y0 = (self.y * h).round().clamp(0.0, h - 1.0);
+        let x1 = ((self.x + self.width) * w).round().clamp(x0 + 1.0, w);
+        let y1 = ((self.y + self.height) * h).round().clamp(y0 + 1.0, h);
+        vk::Rect2D {
+            offset: vk::Offset2D {
+                x: x0 as i32,
+                y: y0 as i32,
+            },
+            extent: vk::Extent2D {
+                width: (x1 - x0) as u32,
+                height: (y1 - y0) as u32,
+            },
+        }
+    }
+}
+
+/// Returns the viewport of every scene of the app; scenes without one get their cell of a grid
+/// over all scenes.
+pub fn get_scene_viewports(app: &mut dyn FeatherApp) -> Vec<Viewport> {
+    let count = app.get_num_scenes_to_render();
+    let grid = Viewport::grid(count);
+    (0..count)
+        .map(|i| app.get_viewport_to_render_scene(i).unwrap_or(grid[i]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        assert_eq!(Viewport::grid(1), vec![Viewport::full()]);
+
+        let grid = Viewport::grid(3);
+        assert_eq!(grid[1], Viewport::new(0.5, 0.0, 0.5, 0.5));
+        assert_eq!(grid[2], Viewport::new(0.0, 0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_to_pixels() {
+        let extent = vk::Extent2D {
+            width: 1000,
+            height: 500,
+        };
+        let rect = Viewport::new(0.75, 0.0, 0.25, 0.3).to_pixels(extent);
+        assert_eq!((rect.offset.x, rect.offset.y), (750, 0));
+        assert_eq!((rect.extent.width, rect.extent.height), (250, 150));
+
+        // Degenerate viewports still cover a pixel.
+        let rect = Viewport::new(1.0, 1.0, 0.0, 0.0).to_pixels(extent);
+        assert_eq!((rect.offset.x, rect.offset.y), (999, 499));
+        assert_eq!((rect.extent.width, rect.extent.height), (1, 1));
+    }
+}
@@ -0,0 +1,5 @@
+// Most of the renderer wraps raw Vulkan calls whose safety rests on the caller passing live
+// handles of the same device, so the unsafe functions are not documented one by one.
+#![allow(clippy::missing_safety_doc)]
+
+pub mod feather;
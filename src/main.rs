@@ -3,8 +3,7 @@ use winit::dpi::LogicalSize;
 use winit::event_loop::EventLoop;
 use winit::window::WindowBuilder;
 
-mod feather;
-use feather::app::App;
+use feather_rs::feather::app::App;
 
 mod testapp;
 use testapp::testapp::TestApp;
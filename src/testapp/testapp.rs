@@ -1,17 +1,24 @@
 use anyhow::Result;
-
-use crate::feather::camera::Camera;
-use crate::feather::featherapp::FeatherApp;
-use crate::feather::math::{Point3, Vec3};
-use crate::feather::meshbuilderobjfile::MeshBuilderObjFile;
-use crate::feather::perspectivecamera::PerspectiveCamera;
-use crate::feather::scene::Scene;
+use cgmath::{Deg, Rotation3};
+
+use feather_rs::feather::camera::Camera;
+use feather_rs::feather::featherapp::FeatherApp;
+use feather_rs::feather::handle::Handle;
+use feather_rs::feather::inputstate::InputState;
+use feather_rs::feather::material::Material;
+use feather_rs::feather::math::{Point3, Quat};
+use feather_rs::feather::meshbuilder::MeshBuilder;
+use feather_rs::feather::meshbuilderobjfile::MeshBuilderObjFile;
+use feather_rs::feather::node::Node;
+use feather_rs::feather::orbitcontroller::OrbitController;
+use feather_rs::feather::perspectivecamera::PerspectiveCamera;
+use feather_rs::feather::scene::Scene;
 
 pub struct TestApp {
     scene: Scene,
     camera: PerspectiveCamera,
-    root_node: usize,
-    room_node: usize,
+    orbit: OrbitController,
+    room_node: Handle<Node>,
 }
 
 impl FeatherApp for TestApp {
@@ -20,13 +27,22 @@ impl FeatherApp for TestApp {
         Ok(())
     }
 
-    fn on_render(&mut self) -> Result<()> {
+    fn on_render(&mut self, _alpha: f32) -> Result<()> {
         log::trace!("on_render called");
         Ok(())
     }
 
-    fn on_update(&mut self, time: f32) -> Result<()> {
-        log::trace!("on_update called with time: {}", time);
+    fn on_update(&mut self, time: f32, delta: f32, frame: u64, input: &InputState) -> Result<()> {
+        log::trace!(
+            "on_update called with time: {}, delta: {}, frame: {}",
+            time,
+            delta,
+            frame
+        );
+        if let Some(room) = self.scene.get_node_mut(self.room_node) {
+            room.set_rotation(Quat::from_angle_z(Deg(10.0) * time));
+        }
+        self.orbit.update(input, delta).apply(&mut self.camera);
         Ok(())
     }
 
@@ -50,31 +66,66 @@ impl FeatherApp for TestApp {
 impl TestApp {
     pub fn new() -> Self {
         let mut scene = Scene::new();
-        let root_node = scene.create_root_node(Some("Scene root".to_string()));
-        let room_node = scene.create_node(Some("Room".to_string()), root_node);
+        let root_node = scene
+            .create_root_node(Some("Scene root".to_string()))
+            .unwrap();
+        let room_node = scene
+            .create_node(Some("Room".to_string()), root_node)
+            .unwrap();
 
         let mut camera = PerspectiveCamera::new();
 
-        camera.set_fov(45.0).set_near_far(0.1, 10.0).set_view(
-            Point3::new(2.0, 2.0, 2.0),
-            Point3::new(0.0, 0.0, 0.0),
-            Vec3::new(0.0, 0.0, 1.0),
-        );
+        camera.set_fov(45.0).set_near_far(0.1, 10.0);
+
+        let mut orbit = OrbitController::new();
+        orbit
+            .look_from(Point3::new(2.0, 2.0, 2.0))
+            .set_distance_limits(0.5, 8.0);
+        orbit.apply(&mut camera);
 
         let meshbuilderobjfile = MeshBuilderObjFile::new("resources/viking_room.obj");
-        let room_mesh = meshbuilderobjfile.build(&mut scene).unwrap();
+        let room_mesh = meshbuilderobjfile.build_into(&mut scene).unwrap();
 
         scene.node_set_mesh(room_node, room_mesh).unwrap();
 
+        let room_texture = scene.load_texture("resources/viking_room.png").unwrap();
+        let room_material = scene
+            .add_material(Material::with_texture(
+                Some("Room".to_string()),
+                room_texture,
+            ))
+            .unwrap();
+        scene.node_set_material(room_node, room_material).unwrap();
+
         //let meshbuildercuboid =
         //    meshbuildercuboid::MeshBuilderCuboid::new_same_walls((-0.5, 0.5), (-0.5, 0.5), (-0.5, 0.5));
-        //let mesh2 = meshbuildercuboid.build(&mut data.scene)?;
+        //let mesh2 = meshbuildercuboid.build_into(&mut data.scene)?;
 
         Self {
             scene,
             camera,
-            root_node,
+            orbit,
             room_node,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_rs::feather::goldenimage::GoldenImage;
+
+    /// Compares a headless frame with `resources/goldens/testapp.png`. To create or update it
+    /// after an intended change in rendering, run on lavapipe, so the reference does not depend
+    /// on a GPU vendor, and commit the PNG:
+    ///
+    /// `VK_ICD_FILENAMES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json FEATHER_UPDATE_GOLDENS=1
+    /// cargo test test_golden_image -- --ignored`
+    #[test]
+    #[ignore = "needs a Vulkan driver such as lavapipe"]
+    fn test_golden_image() {
+        let mut golden = GoldenImage::new("resources/goldens/testapp.png");
+        golden.set_size(320, 240).set_frames(3).set_time(1.5);
+        unsafe { golden.check_app(Box::new(TestApp::new())) }.unwrap();
+    }
+}